@@ -0,0 +1,203 @@
+use crate::actors::bullet_pattern::PatternEmitter;
+use crate::behaviors::model::BehaviorTreeType;
+use serde::Deserialize;
+use std::io::Read;
+use thiserror::Error;
+
+// boss timings are in ms
+const BOSS_INTRO_DURATION: f32 = 3000.0;
+const BOSS_DEFEAT_DURATION: f32 = 3000.0;
+
+#[derive(Debug, Error)]
+pub enum BossDefinitionError {
+    #[error("failed to open boss definitions: {0}")]
+    Open(#[from] ggez::GameError),
+    #[error("failed to read boss definitions: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse boss definitions: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("no bosses are defined")]
+    Empty,
+    #[error("boss `{0}` has no phases")]
+    NoPhases(String),
+    #[error("boss `{0}` must start at hp_threshold 1.0 and list its phases in descending order")]
+    PhaseOrder(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BossMovement {
+    /// Let the boss's behavior tree pick where to go
+    Ai,
+    /// Sweep left and right across the top of the screen
+    Strafe,
+    /// Shadow the player's x position from above
+    Hover,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BossPhase {
+    // the phase starts once hp drops to this fraction of max hp
+    pub hp_threshold: f32,
+    pub movement: BossMovement,
+    // name of the pattern in resources/bullet_patterns.toml
+    pub pattern: String,
+    // ms between the end of one pattern and the start of the next
+    pub attack_cooldown: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BossDefinition {
+    pub name: String,
+    pub hp: f32,
+    pub velocity: f32,
+    // ms into the fight before the boss enrages
+    pub enrage_after: f32,
+    pub behavior_tree: BehaviorTreeType,
+    pub phases: Vec<BossPhase>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BossDefinitionFile {
+    pub bosses: Vec<BossDefinition>,
+}
+
+pub fn parse_boss_definitions(source: &str) -> Result<Vec<BossDefinition>, BossDefinitionError> {
+    let file: BossDefinitionFile = toml::from_str(source)?;
+    if file.bosses.is_empty() {
+        return Err(BossDefinitionError::Empty);
+    }
+    for boss in &file.bosses {
+        if boss.phases.is_empty() {
+            return Err(BossDefinitionError::NoPhases(boss.name.clone()));
+        }
+        // BossState::update picks the last phase whose threshold the hp has dropped to
+        let is_descending = boss
+            .phases
+            .windows(2)
+            .all(|pair| pair[1].hp_threshold < pair[0].hp_threshold);
+        if boss.phases[0].hp_threshold != 1.0 || !is_descending {
+            return Err(BossDefinitionError::PhaseOrder(boss.name.clone()));
+        }
+    }
+    Ok(file.bosses)
+}
+
+pub fn load_boss_definitions(
+    ctx: &ggez::Context,
+    path: &str,
+) -> Result<Vec<BossDefinition>, BossDefinitionError> {
+    let mut source = String::new();
+    ctx.fs.open(path)?.read_to_string(&mut source)?;
+    parse_boss_definitions(&source)
+}
+
+pub struct BossState {
+    pub definition: BossDefinition,
+    pub max_hp: f32,
+    pub velocity: f32,
+    pub phase: usize,
+    pub elapsed: f32,
+    pub intro_remaining: f32,
+    pub defeat_remaining: Option<f32>,
+    pub pattern_emitter: Option<PatternEmitter>,
+}
+
+impl BossState {
+    pub fn new(definition: BossDefinition, max_hp: f32, velocity: f32) -> BossState {
+        BossState {
+            definition,
+            max_hp,
+            velocity,
+            phase: 0,
+            elapsed: 0.0,
+            intro_remaining: BOSS_INTRO_DURATION,
+            defeat_remaining: None,
+            pattern_emitter: None,
+        }
+    }
+
+    pub fn current_phase(&self) -> &BossPhase {
+        &self.definition.phases[self.phase]
+    }
+
+    pub fn is_in_intro(&self) -> bool {
+        self.intro_remaining > 0.0
+    }
+
+    pub fn is_defeated(&self) -> bool {
+        self.defeat_remaining.is_some()
+    }
+
+    pub fn is_defeat_finished(&self) -> bool {
+        self.defeat_remaining
+            .is_some_and(|remaining| remaining <= 0.0)
+    }
+
+    pub fn is_enraged(&self) -> bool {
+        self.elapsed >= self.definition.enrage_after
+    }
+
+    pub fn start_defeat(&mut self) {
+        self.defeat_remaining = Some(BOSS_DEFEAT_DURATION);
+    }
+
+    pub fn get_velocity(&self) -> f32 {
+        match self.is_enraged() {
+            true => self.velocity * 1.5,
+            false => self.velocity,
+        }
+    }
+
+    /// Advances the boss timers, returns true when the boss moved on to a new phase
+    pub fn update(&mut self, hp: f32, dt: f32) -> bool {
+        if let Some(remaining) = &mut self.defeat_remaining {
+            *remaining -= dt;
+            return false;
+        }
+        if self.is_in_intro() {
+            self.intro_remaining -= dt;
+            return false;
+        }
+        self.elapsed += dt;
+        let hp_fraction = hp / self.max_hp;
+        let next_phase = self
+            .definition
+            .phases
+            .iter()
+            .rposition(|phase| hp_fraction <= phase.hp_threshold)
+            .unwrap_or(0);
+        if next_phase > self.phase {
+            self.phase = next_phase;
+            return true;
+        }
+        false
+    }
+
+    pub fn get_attack_cooldown(&self) -> f32 {
+        match self.is_enraged() {
+            true => self.current_phase().attack_cooldown * 0.5,
+            false => self.current_phase().attack_cooldown,
+        }
+    }
+}
+
+pub fn get_boss_movement_target(
+    movement: BossMovement,
+    boss_position: (f32, f32),
+    player_position: (f32, f32),
+    elapsed: f32,
+    screen_width: f32,
+) -> Option<(f32, f32)> {
+    match movement {
+        BossMovement::Ai => None,
+        BossMovement::Strafe => {
+            let sweep = (elapsed / 2000.0).sin();
+            Some((
+                screen_width / 2.0 + sweep * (screen_width / 2.0 - 150.0),
+                150.0,
+            ))
+        }
+        BossMovement::Hover => Some((player_position.0, boss_position.1.min(250.0))),
+    }
+}
@@ -0,0 +1,252 @@
+use crate::actors::models::Actor;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Read;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum BulletPatternError {
+    #[error("failed to open bullet patterns: {0}")]
+    Open(#[from] ggez::GameError),
+    #[error("failed to read bullet patterns: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse bullet patterns: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("bullet pattern `{0}` has no steps")]
+    Empty(String),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BulletPatternFile {
+    pub patterns: Vec<BulletPattern>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BulletPattern {
+    pub name: String,
+    pub steps: Vec<PatternStep>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PatternStep {
+    // ms after the pattern starts before the first volley of this step
+    #[serde(default)]
+    pub delay: f32,
+    #[serde(default = "default_repeat")]
+    pub repeat: u32,
+    // ms between repeats
+    #[serde(default)]
+    pub interval: f32,
+    #[serde(default)]
+    pub heavy: bool,
+    pub speed: Option<f32>,
+    pub modifier: Option<ProjectileModifier>,
+    #[serde(flatten)]
+    pub shape: PatternShape,
+}
+
+fn default_repeat() -> u32 {
+    1
+}
+
+/// Angles are in degrees, relative to the direction of the aim point
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "shape", rename_all = "snake_case")]
+pub enum PatternShape {
+    /// Evenly spaced burst all around the emitter
+    Radial { count: u32 },
+    /// Radial burst that rotates by `turn` every repeat
+    Spiral { count: u32, turn: f32 },
+    /// Fan of `count` projectiles spread across `spread` around the aim point
+    Aimed {
+        count: u32,
+        #[serde(default)]
+        spread: f32,
+    },
+    /// Fan that sweeps back and forth across `amplitude` over the repeats
+    Wave {
+        count: u32,
+        #[serde(default)]
+        spread: f32,
+        amplitude: f32,
+        frequency: f32,
+    },
+    /// Radial burst with `gap` missing slots, centred `gap_angle` away from the aim point
+    Ring {
+        count: u32,
+        gap: u32,
+        #[serde(default)]
+        gap_angle: f32,
+    },
+}
+
+/// Changes a projectile's flight once `after` ms have passed since it was fired
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ProjectileModifier {
+    pub after: f32,
+    pub speed: Option<f32>,
+    pub turn: Option<f32>,
+    #[serde(default)]
+    pub aim_at_player: bool,
+}
+
+pub struct PatternShot {
+    pub target: (f32, f32),
+    pub heavy: bool,
+    pub speed: Option<f32>,
+    pub modifier: Option<ProjectileModifier>,
+}
+
+pub fn parse_bullet_patterns(
+    source: &str,
+) -> Result<HashMap<String, BulletPattern>, BulletPatternError> {
+    let file: BulletPatternFile = toml::from_str(source)?;
+    let mut patterns = HashMap::new();
+    for pattern in file.patterns {
+        if pattern.steps.is_empty() {
+            return Err(BulletPatternError::Empty(pattern.name));
+        }
+        patterns.insert(pattern.name.clone(), pattern);
+    }
+    Ok(patterns)
+}
+
+pub fn load_bullet_patterns(
+    ctx: &ggez::Context,
+    path: &str,
+) -> Result<HashMap<String, BulletPattern>, BulletPatternError> {
+    let mut source = String::new();
+    ctx.fs.open(path)?.read_to_string(&mut source)?;
+    parse_bullet_patterns(&source)
+}
+
+/// Plays a pattern over time, handing out the shots that are due each frame
+pub struct PatternEmitter {
+    pattern: BulletPattern,
+    elapsed: f32,
+    // how many repeats of each step have been fired so far
+    fired: Vec<u32>,
+}
+
+impl PatternEmitter {
+    pub fn new(pattern: BulletPattern) -> PatternEmitter {
+        let fired = vec![0; pattern.steps.len()];
+        PatternEmitter {
+            pattern,
+            elapsed: 0.0,
+            fired,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.pattern
+            .steps
+            .iter()
+            .zip(&self.fired)
+            .all(|(step, fired)| *fired >= step.repeat)
+    }
+
+    pub fn update(&mut self, dt: f32, origin: (f32, f32), aim: (f32, f32)) -> Vec<PatternShot> {
+        self.elapsed += dt;
+        let aim_angle = (aim.1 - origin.1).atan2(aim.0 - origin.0).to_degrees();
+        let mut shots = vec![];
+        for (step, fired) in self.pattern.steps.iter().zip(self.fired.iter_mut()) {
+            while *fired < step.repeat && self.elapsed >= step.delay + step.interval * *fired as f32
+            {
+                for angle in get_step_angles(&step.shape, *fired, aim_angle) {
+                    let radians = angle.to_radians();
+                    shots.push(PatternShot {
+                        target: (
+                            origin.0 + radians.cos() * 10000.0,
+                            origin.1 + radians.sin() * 10000.0,
+                        ),
+                        heavy: step.heavy,
+                        speed: step.speed,
+                        modifier: step.modifier,
+                    });
+                }
+                *fired += 1;
+            }
+        }
+        shots
+    }
+}
+
+fn get_fan_angles(count: u32, spread: f32, centre: f32) -> Vec<f32> {
+    if count <= 1 {
+        return vec![centre];
+    }
+    let step = spread / (count - 1) as f32;
+    (0..count)
+        .map(|i| centre - spread / 2.0 + step * i as f32)
+        .collect()
+}
+
+fn get_step_angles(shape: &PatternShape, repeat: u32, aim_angle: f32) -> Vec<f32> {
+    match shape {
+        PatternShape::Radial { count } => (0..*count)
+            .map(|i| aim_angle + 360.0 * i as f32 / *count as f32)
+            .collect(),
+        PatternShape::Spiral { count, turn } => (0..*count)
+            .map(|i| aim_angle + turn * repeat as f32 + 360.0 * i as f32 / *count as f32)
+            .collect(),
+        PatternShape::Aimed { count, spread } => get_fan_angles(*count, *spread, aim_angle),
+        PatternShape::Wave {
+            count,
+            spread,
+            amplitude,
+            frequency,
+        } => {
+            let sweep = amplitude * (repeat as f32 * frequency).sin();
+            get_fan_angles(*count, *spread, aim_angle + sweep)
+        }
+        PatternShape::Ring {
+            count,
+            gap,
+            gap_angle,
+        } => {
+            let slot = 360.0 / *count as f32;
+            let gap_centre = aim_angle + gap_angle;
+            // even gaps straddle the gap centre, odd gaps have a slot sitting right on it
+            let offset = match gap % 2 == 0 {
+                true => 0.5,
+                false => 0.0,
+            };
+            (0..*count)
+                .filter(|i| {
+                    let slots_from_gap =
+                        (*i as f32 + offset).min(*count as f32 - *i as f32 - offset);
+                    slots_from_gap >= *gap as f32 / 2.0
+                })
+                .map(|i| gap_centre + slot * (i as f32 + offset))
+                .collect()
+        }
+    }
+}
+
+/// Applies a projectile's pending modifier once its timer runs out
+pub fn handle_projectile_modifier(projectile: &mut Actor, dt: f32, player_position: (f32, f32)) {
+    let modifier = match &mut projectile.projectile_modifier {
+        Some(modifier) => modifier,
+        None => return,
+    };
+    modifier.after -= dt;
+    if modifier.after > 0.0 {
+        return;
+    }
+    let modifier = *modifier;
+    projectile.projectile_modifier = None;
+
+    if let Some(speed) = modifier.speed {
+        projectile.velocity = speed;
+    }
+    let mut angle = (projectile.target_y - projectile.y).atan2(projectile.target_x - projectile.x);
+    if modifier.aim_at_player {
+        angle = (player_position.1 - projectile.y).atan2(player_position.0 - projectile.x);
+    }
+    if let Some(turn) = modifier.turn {
+        angle += turn.to_radians();
+    }
+    projectile.target_x = projectile.x + angle.cos() * 10000.0;
+    projectile.target_y = projectile.y + angle.sin() * 10000.0;
+}
@@ -1,6 +1,158 @@
-use crate::actors::models::{Actor, ActorType};
+use crate::actors::boss::BossDefinition;
+use crate::actors::models::{next_entity_id, Actor, ActorType, EntityId};
 use crate::behaviors::enemy_ai::model::EnemyAi;
+use crate::behaviors::model::BehaviorTreeType;
 use ggez::graphics::{Color, Image, Mesh};
+use serde::Deserialize;
+use std::io::Read;
+use thiserror::Error;
+
+// kamikazes blow up on contact or death, hurting the player anywhere within the blast
+pub const KAMIKAZE_BLAST_RADIUS: f32 = 160.0;
+pub const KAMIKAZE_BLAST_DAMAGE: f32 = 25.0;
+// ms the blast stays on screen
+pub const BLAST_DURATION: f32 = 400.0;
+// carrier timings are in ms
+pub const CARRIER_LAUNCH_INTERVAL: f32 = 3500.0;
+pub const CARRIER_MAX_FIGHTERS: usize = 4;
+// how long fighters flee after losing their carrier before they self-destruct
+pub const FIGHTER_ORPHAN_DURATION: f32 = 2500.0;
+// ms between the hazards a mine-layer drops while it moves
+pub const MINE_LAYER_DROP_INTERVAL: f32 = 1800.0;
+
+#[derive(Debug, Error)]
+pub enum EnemySettingsError {
+    #[error("failed to open enemy settings: {0}")]
+    Open(#[from] ggez::GameError),
+    #[error("failed to read enemy settings: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse enemy settings: {0}")]
+    Parse(#[from] toml::de::Error),
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct SplitterSettings {
+    // pieces a splitter breaks into, pieces split again until they reach max_depth
+    pub count: usize,
+    pub max_depth: u32,
+    // every split the pieces get this fraction of their parent's hp and size
+    pub scale: f32,
+    pub speed_up: f32,
+    // px/s the pieces are flung away from the hit
+    pub scatter_speed: f32,
+}
+
+/// Enemy archetype tunables, loaded from resources/enemies.toml
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnemySettings {
+    pub splitter: SplitterSettings,
+}
+
+pub fn load_enemy_settings(
+    ctx: &ggez::Context,
+    path: &str,
+) -> Result<EnemySettings, EnemySettingsError> {
+    let mut source = String::new();
+    ctx.fs.open(path)?.read_to_string(&mut source)?;
+    Ok(toml::from_str(&source)?)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EnemyKind {
+    Standard,
+    /// Fast and fragile, rams the player and explodes
+    Kamikaze,
+    /// Slow and tough, launches fighters
+    Carrier,
+    /// Launched by a carrier, small and quick
+    Fighter,
+    /// Breaks into smaller, faster copies of itself when destroyed
+    Splitter,
+    /// Leaves mines, slow fields and clouds behind it
+    MineLayer,
+}
+
+/// Chance of a wave enemy being a kamikaze, they start showing up from the third wave
+pub fn get_kamikaze_spawn_chance(wave_count: f32) -> f32 {
+    match wave_count < 3.0 {
+        true => 0.0,
+        false => (0.05 * (wave_count - 2.0)).min(0.25),
+    }
+}
+
+/// Chance of a wave enemy being a carrier, they start showing up from the fourth wave
+pub fn get_carrier_spawn_chance(wave_count: f32) -> f32 {
+    match wave_count < 4.0 {
+        true => 0.0,
+        false => (0.03 * (wave_count - 3.0)).min(0.12),
+    }
+}
+
+/// Chance of a wave enemy being a splitter, they start showing up from the second wave
+pub fn get_splitter_spawn_chance(wave_count: f32) -> f32 {
+    match wave_count < 2.0 {
+        true => 0.0,
+        false => (0.04 * (wave_count - 1.0)).min(0.2),
+    }
+}
+
+/// Chance of a wave enemy being a mine-layer, they start showing up from the fifth wave
+pub fn get_mine_layer_spawn_chance(wave_count: f32) -> f32 {
+    match wave_count < 5.0 {
+        true => 0.0,
+        false => (0.03 * (wave_count - 4.0)).min(0.12),
+    }
+}
+
+/// How many times a splitter has already split, the hp it started with and the personality
+/// its pieces keep
+#[derive(Debug, Clone, Copy)]
+pub struct Splitter {
+    pub depth: u32,
+    pub max_hp: f32,
+    pub personality: BehaviorTreeType,
+}
+
+/// Headings for the pieces of a destroyed splitter, fanned out around the direction the
+/// killing blow travelled
+pub fn get_split_directions(impact_direction: (f32, f32), count: usize) -> Vec<(f32, f32)> {
+    let heading = impact_direction.1.atan2(impact_direction.0);
+    let spread = 2.0 * std::f32::consts::FRAC_PI_3;
+    (0..count)
+        .map(|i| {
+            let offset = match count > 1 {
+                true => spread * (i as f32 / (count - 1) as f32 - 0.5),
+                false => 0.0,
+            };
+            ((heading + offset).cos(), (heading + offset).sin())
+        })
+        .collect()
+}
+
+/// Fighters a carrier has launched that are still alive
+pub struct Carrier {
+    pub fighters: Vec<EntityId>,
+    pub launch_cd: f32,
+}
+
+impl Default for Carrier {
+    fn default() -> Self {
+        Carrier {
+            fighters: vec![],
+            launch_cd: CARRIER_LAUNCH_INTERVAL,
+        }
+    }
+}
+
+/// Expanding ring left behind by an explosion, like a kamikaze, a fighter that self-destructed
+/// or a mine
+pub struct Blast {
+    pub x: f32,
+    pub y: f32,
+    // px the ring grows to
+    pub radius: f32,
+    pub remaining: f32,
+}
 
 pub fn create_enemy(
     x: f32,
@@ -16,7 +168,8 @@ pub fn create_enemy(
     // log(n) max velocity of 550.0
     let velocity = 600.0_f32.ln() * hp_modifier.unwrap_or(1.0) + 100.0;
     Actor {
-        actor_type: ActorType::Enemy,
+        id: next_entity_id(),
+        actor_type: ActorType::Enemy(EnemyKind::Standard),
         x,
         y,
         target_x: x,
@@ -29,12 +182,110 @@ pub fn create_enemy(
         is_taking_damage: None,
         attack_cooldown,
         ai,
+        projectile_modifier: None,
+        current_velocity: (0.0, 0.0),
+        steering_goal: None,
     }
 }
+pub fn create_kamikaze_enemy(
+    x: f32,
+    y: f32,
+    mesh: Mesh,
+    hp_modifier: Option<f32>,
+    ai: Option<Box<dyn EnemyAi>>,
+) -> Actor {
+    let mut enemy = create_enemy(x, y, Color::RED, mesh, None, hp_modifier, None, ai);
+    enemy.actor_type = ActorType::Enemy(EnemyKind::Kamikaze);
+    enemy.hp *= 0.6;
+    enemy.velocity *= 1.4;
+    enemy
+}
+pub fn create_carrier_enemy(
+    x: f32,
+    y: f32,
+    mesh: Mesh,
+    hp_modifier: Option<f32>,
+    attack_cooldown: Option<f32>,
+    ai: Option<Box<dyn EnemyAi>>,
+) -> Actor {
+    let mut enemy = create_enemy(
+        x,
+        y,
+        Color::RED,
+        mesh,
+        None,
+        hp_modifier,
+        attack_cooldown,
+        ai,
+    );
+    enemy.actor_type = ActorType::Enemy(EnemyKind::Carrier);
+    enemy.hp *= 6.0;
+    enemy.velocity *= 0.4;
+    enemy
+}
+pub fn create_fighter_enemy(
+    x: f32,
+    y: f32,
+    mesh: Mesh,
+    hp_modifier: Option<f32>,
+    ai: Option<Box<dyn EnemyAi>>,
+) -> Actor {
+    let mut enemy = create_enemy(x, y, Color::RED, mesh, None, hp_modifier, Some(1000.0), ai);
+    enemy.actor_type = ActorType::Enemy(EnemyKind::Fighter);
+    enemy.hp *= 0.3;
+    enemy.velocity *= 1.3;
+    enemy
+}
+pub fn create_mine_layer_enemy(
+    x: f32,
+    y: f32,
+    mesh: Mesh,
+    hp_modifier: Option<f32>,
+    attack_cooldown: Option<f32>,
+    ai: Option<Box<dyn EnemyAi>>,
+) -> Actor {
+    let mut enemy = create_enemy(
+        x,
+        y,
+        Color::RED,
+        mesh,
+        None,
+        hp_modifier,
+        attack_cooldown,
+        ai,
+    );
+    enemy.actor_type = ActorType::Enemy(EnemyKind::MineLayer);
+    enemy.hp *= 1.5;
+    enemy.velocity *= 0.8;
+    enemy
+}
+pub fn create_splitter_enemy(
+    x: f32,
+    y: f32,
+    mesh: Mesh,
+    hp_modifier: Option<f32>,
+    attack_cooldown: Option<f32>,
+    ai: Option<Box<dyn EnemyAi>>,
+) -> Actor {
+    let mut enemy = create_enemy(
+        x,
+        y,
+        Color::RED,
+        mesh,
+        None,
+        hp_modifier,
+        attack_cooldown,
+        ai,
+    );
+    enemy.actor_type = ActorType::Enemy(EnemyKind::Splitter);
+    enemy.hp *= 2.0;
+    enemy
+}
 pub fn create_boss_enemy(
     x: f32,
     y: f32,
     color: Color,
+    definition: &BossDefinition,
     hp_scale_factor: f32,
     velocity_scale_factor: f32,
     mesh: Mesh,
@@ -43,18 +294,22 @@ pub fn create_boss_enemy(
     ai: Option<Box<dyn EnemyAi>>,
 ) -> Actor {
     Actor {
-        actor_type: ActorType::Enemy,
+        id: next_entity_id(),
+        actor_type: ActorType::BossEnemy,
         x,
         y,
         target_x: x,
         target_y: y,
-        velocity: 100.0 * velocity_scale_factor,
+        velocity: definition.velocity * velocity_scale_factor,
         color,
-        hp: 500.0 * hp_scale_factor,
+        hp: definition.hp * hp_scale_factor,
         bounding_box: mesh,
         image,
         is_taking_damage: None,
         attack_cooldown,
         ai,
+        projectile_modifier: None,
+        current_velocity: (0.0, 0.0),
+        steering_goal: None,
     }
 }
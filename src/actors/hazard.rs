@@ -0,0 +1,96 @@
+use crate::actors::models::{next_entity_id, Actor, ActorType};
+use ggez::graphics;
+use ggez::graphics::Mesh;
+use rand::Rng;
+
+// Hazards reuse `hp` as their remaining lifetime in seconds, same as pickups
+// seconds a mine has to be down before the player can set it off
+const MINE_ARM_TIME: f32 = 0.8;
+// px from a mine the player sets it off at
+pub const MINE_TRIGGER_RADIUS: f32 = 90.0;
+// px from a mine a player shot detonates it at
+pub const MINE_HIT_RADIUS: f32 = 30.0;
+// mine blasts hurt the player and enemies alike
+pub const MINE_BLAST_RADIUS: f32 = 130.0;
+pub const MINE_BLAST_DAMAGE: f32 = 20.0;
+// px/s the player can move at most inside a slow field
+pub const SLOW_FIELD_MAX_SPEED: f32 = 300.0;
+// damage per second to anyone inside a cloud
+pub const CLOUD_DAMAGE: f32 = 8.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HazardKind {
+    /// Slows the player down while inside it
+    SlowField,
+    /// Hurts anyone inside it over time
+    Cloud,
+    /// Explodes when the player comes close or shoots it
+    Mine,
+}
+
+impl HazardKind {
+    pub fn color(&self) -> graphics::Color {
+        match self {
+            HazardKind::SlowField => graphics::Color::new(0.3, 0.5, 1.0, 0.25),
+            HazardKind::Cloud => graphics::Color::new(0.5, 0.9, 0.2, 0.3),
+            HazardKind::Mine => graphics::Color::from_rgb(240, 60, 60),
+        }
+    }
+
+    /// px around the hazard it covers
+    pub fn radius(&self) -> f32 {
+        match self {
+            HazardKind::SlowField => 110.0,
+            HazardKind::Cloud => 90.0,
+            HazardKind::Mine => 10.0,
+        }
+    }
+
+    /// Seconds the hazard stays on the field
+    pub fn lifetime(&self) -> f32 {
+        match self {
+            HazardKind::SlowField => 8.0,
+            HazardKind::Cloud => 6.0,
+            HazardKind::Mine => 15.0,
+        }
+    }
+}
+
+pub fn roll_hazard_kind() -> HazardKind {
+    match rand::thread_rng().gen_range(0..3) {
+        0 => HazardKind::SlowField,
+        1 => HazardKind::Cloud,
+        _ => HazardKind::Mine,
+    }
+}
+
+pub fn create_hazard(x: f32, y: f32, hazard_kind: HazardKind, mesh: Mesh) -> Actor {
+    Actor {
+        id: next_entity_id(),
+        actor_type: ActorType::Hazard(hazard_kind),
+        x,
+        y,
+        // hazards stay where they were dropped
+        target_x: x,
+        target_y: y,
+        velocity: 0.0,
+        color: hazard_kind.color(),
+        hp: hazard_kind.lifetime(),
+        bounding_box: mesh,
+        image: None,
+        is_taking_damage: None,
+        attack_cooldown: None,
+        ai: None,
+        projectile_modifier: None,
+        current_velocity: (0.0, 0.0),
+        steering_goal: None,
+    }
+}
+
+pub fn is_in_range(hazard: &Actor, point: (f32, f32), radius: f32) -> bool {
+    ((point.0 - hazard.x).powi(2) + (point.1 - hazard.y).powi(2)).sqrt() < radius
+}
+
+pub fn is_mine_armed(mine: &Actor) -> bool {
+    HazardKind::Mine.lifetime() - mine.hp >= MINE_ARM_TIME
+}
@@ -1,4 +1,10 @@
+pub mod boss;
+pub mod bullet_pattern;
 pub mod enemy;
+pub mod hazard;
 pub mod models;
+pub mod pickup;
 pub mod player;
 pub mod projectile;
+pub mod ram;
+pub mod shield;
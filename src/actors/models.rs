@@ -1,19 +1,31 @@
+use crate::actors::bullet_pattern::ProjectileModifier;
+use crate::actors::enemy::EnemyKind;
+use crate::actors::hazard::HazardKind;
+use crate::actors::pickup::PickupType;
 use crate::behaviors::enemy_ai::model::EnemyAi;
+use crate::behaviors::steering::SteeringGoal;
 use ggez::graphics;
 use ggez::graphics::{Color, Drawable};
 use ggez::mint::Point2;
 use std::ops::Deref;
 
+pub use crate::entity::{next_entity_id, EntityId};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ActorType {
     Player,
-    Enemy,
+    Enemy(EnemyKind),
     BossEnemy,
     PlayerProjectile,
+    // the special attack, breaks enemy shields
+    PlayerAltProjectile,
     EnemyProjectile,
+    Pickup(PickupType),
+    Hazard(HazardKind),
 }
 
 pub struct Actor {
+    pub id: EntityId,
     pub actor_type: ActorType,
     pub x: f32,
     pub y: f32,
@@ -27,6 +39,10 @@ pub struct Actor {
     pub is_taking_damage: Option<f32>,
     pub attack_cooldown: Option<f32>,
     pub ai: Option<Box<dyn EnemyAi>>,
+    pub projectile_modifier: Option<ProjectileModifier>,
+    // actual velocity vector, `velocity` above is the top speed
+    pub current_velocity: (f32, f32),
+    pub steering_goal: Option<SteeringGoal>,
 }
 
 pub fn get_player_polygon_mesh_vertices() -> Vec<Point2<f32>> {
@@ -68,6 +84,77 @@ pub fn get_enemy_polygon_mesh_vertices() -> Vec<Point2<f32>> {
         Point2 { x: 0.0, y: 25.0 },
     ]
 }
+pub fn get_kamikaze_polygon_mesh_vertices() -> Vec<Point2<f32>> {
+    vec![
+        // nose
+        Point2 { x: 0.0, y: 30.0 },
+        Point2 { x: 5.0, y: 18.0 },
+        Point2 { x: 12.0, y: 4.0 },
+        Point2 { x: 4.0, y: 8.0 },
+        Point2 { x: 0.0, y: 2.0 },
+        Point2 { x: -4.0, y: 8.0 },
+        Point2 { x: -12.0, y: 4.0 },
+        Point2 { x: -5.0, y: 18.0 },
+        Point2 { x: 0.0, y: 30.0 },
+    ]
+}
+pub fn get_carrier_polygon_mesh_vertices() -> Vec<Point2<f32>> {
+    vec![
+        Point2 { x: 0.0, y: 40.0 },
+        Point2 { x: 20.0, y: 36.0 },
+        Point2 { x: 34.0, y: 24.0 },
+        Point2 { x: 40.0, y: 6.0 },
+        // launch bay
+        Point2 { x: 10.0, y: 6.0 },
+        Point2 { x: 10.0, y: 14.0 },
+        Point2 { x: -10.0, y: 14.0 },
+        Point2 { x: -10.0, y: 6.0 },
+        Point2 { x: -40.0, y: 6.0 },
+        Point2 { x: -34.0, y: 24.0 },
+        Point2 { x: -20.0, y: 36.0 },
+        Point2 { x: 0.0, y: 40.0 },
+    ]
+}
+pub fn get_mine_layer_polygon_mesh_vertices() -> Vec<Point2<f32>> {
+    vec![
+        Point2 { x: 0.0, y: 20.0 },
+        Point2 { x: 16.0, y: 14.0 },
+        Point2 { x: 22.0, y: 0.0 },
+        // mine hatch at the back
+        Point2 { x: 8.0, y: -4.0 },
+        Point2 { x: 8.0, y: -12.0 },
+        Point2 { x: -8.0, y: -12.0 },
+        Point2 { x: -8.0, y: -4.0 },
+        Point2 { x: -22.0, y: 0.0 },
+        Point2 { x: -16.0, y: 14.0 },
+        Point2 { x: 0.0, y: 20.0 },
+    ]
+}
+pub fn get_fighter_polygon_mesh_vertices() -> Vec<Point2<f32>> {
+    vec![
+        Point2 { x: 0.0, y: 14.0 },
+        Point2 { x: 3.0, y: 10.0 },
+        Point2 { x: 8.0, y: 4.0 },
+        Point2 { x: 2.0, y: 6.0 },
+        Point2 { x: -2.0, y: 6.0 },
+        Point2 { x: -8.0, y: 4.0 },
+        Point2 { x: -3.0, y: 10.0 },
+        Point2 { x: 0.0, y: 14.0 },
+    ]
+}
+pub fn get_splitter_polygon_mesh_vertices() -> Vec<Point2<f32>> {
+    vec![
+        Point2 { x: 0.0, y: 24.0 },
+        Point2 { x: 14.0, y: 16.0 },
+        Point2 { x: 18.0, y: 2.0 },
+        Point2 { x: 10.0, y: -12.0 },
+        Point2 { x: 0.0, y: -6.0 },
+        Point2 { x: -10.0, y: -12.0 },
+        Point2 { x: -18.0, y: 2.0 },
+        Point2 { x: -14.0, y: 16.0 },
+        Point2 { x: 0.0, y: 24.0 },
+    ]
+}
 pub fn get_boss_enemy_polygon_mesh_vertices() -> Vec<Point2<f32>> {
     vec![
         Point2 { x: 0.0, y: 25.0 },
@@ -138,6 +225,68 @@ pub fn create_enemy_spaceship_mesh(ctx: &mut ggez::Context) -> graphics::Mesh {
     mesh
 }
 
+pub fn create_kamikaze_spaceship_mesh(ctx: &mut ggez::Context) -> graphics::Mesh {
+    let mesh = graphics::Mesh::new_polygon(
+        ctx,
+        graphics::DrawMode::fill(),
+        get_kamikaze_polygon_mesh_vertices().deref(),
+        Color::from_rgb(250, 170, 40),
+    )
+    .expect("Failed to create kamikaze spaceship mesh");
+    mesh
+}
+
+pub fn create_carrier_spaceship_mesh(ctx: &mut ggez::Context) -> graphics::Mesh {
+    let mesh = graphics::Mesh::new_polygon(
+        ctx,
+        graphics::DrawMode::fill(),
+        get_carrier_polygon_mesh_vertices().deref(),
+        Color::from_rgb(140, 110, 160),
+    )
+    .expect("Failed to create carrier spaceship mesh");
+    mesh
+}
+
+pub fn create_fighter_spaceship_mesh(ctx: &mut ggez::Context) -> graphics::Mesh {
+    let mesh = graphics::Mesh::new_polygon(
+        ctx,
+        graphics::DrawMode::fill(),
+        get_fighter_polygon_mesh_vertices().deref(),
+        Color::from_rgb(190, 160, 220),
+    )
+    .expect("Failed to create fighter spaceship mesh");
+    mesh
+}
+
+/// `scale` shrinks the mesh for the pieces of a split splitter
+pub fn create_splitter_spaceship_mesh(ctx: &mut ggez::Context, scale: f32) -> graphics::Mesh {
+    let vertices: Vec<Point2<f32>> = get_splitter_polygon_mesh_vertices()
+        .iter()
+        .map(|point| Point2 {
+            x: point.x * scale,
+            y: point.y * scale,
+        })
+        .collect();
+    graphics::Mesh::new_polygon(
+        ctx,
+        graphics::DrawMode::fill(),
+        &vertices,
+        Color::from_rgb(110, 200, 120),
+    )
+    .expect("Failed to create splitter spaceship mesh")
+}
+
+pub fn create_mine_layer_spaceship_mesh(ctx: &mut ggez::Context) -> graphics::Mesh {
+    let mesh = graphics::Mesh::new_polygon(
+        ctx,
+        graphics::DrawMode::fill(),
+        get_mine_layer_polygon_mesh_vertices().deref(),
+        Color::from_rgb(200, 200, 90),
+    )
+    .expect("Failed to create mine-layer spaceship mesh");
+    mesh
+}
+
 pub fn create_boss_enemy_spaceship_mesh(ctx: &mut ggez::Context) -> graphics::Mesh {
     let mesh = graphics::Mesh::new_polygon(
         ctx,
@@ -191,3 +340,27 @@ pub fn create_player_alt_projectile_mesh(ctx: &mut ggez::Context) -> graphics::M
     .expect("Failed to create player alt projectile mesh");
     projectile_mesh
 }
+
+pub fn create_hazard_mesh(ctx: &mut ggez::Context, hazard_kind: HazardKind) -> graphics::Mesh {
+    graphics::Mesh::new_circle(
+        ctx,
+        graphics::DrawMode::fill(),
+        Point2 { x: 0.0, y: 0.0 },
+        hazard_kind.radius(),
+        0.5,
+        hazard_kind.color(),
+    )
+    .expect("Failed to create hazard mesh")
+}
+
+pub fn create_pickup_mesh(ctx: &mut ggez::Context, color: Color) -> graphics::Mesh {
+    graphics::Mesh::new_circle(
+        ctx,
+        graphics::DrawMode::fill(),
+        Point2 { x: 0.0, y: 0.0 },
+        12.0,
+        0.5,
+        color,
+    )
+    .expect("Failed to create pickup mesh")
+}
@@ -0,0 +1,92 @@
+use crate::actors::models::{next_entity_id, Actor, ActorType};
+use ggez::graphics;
+use ggez::graphics::Mesh;
+use rand::Rng;
+
+pub const DEFAULT_PICKUP_DROP_CHANCE: f32 = 0.15;
+// Pickups reuse `hp` as their remaining lifetime in seconds, same as projectiles
+const PICKUP_LIFETIME: f32 = 10.0;
+const PICKUP_DRIFT_VELOCITY: f32 = 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PickupType {
+    Health,
+    WeaponUpgrade,
+    Shield,
+    SpecialReset,
+    ScoreMultiplier,
+    RapidFire,
+}
+
+impl PickupType {
+    pub fn color(&self) -> graphics::Color {
+        match self {
+            PickupType::Health => graphics::Color::from_rgb(80, 220, 80),
+            PickupType::WeaponUpgrade => graphics::Color::from_rgb(250, 200, 50),
+            PickupType::Shield => graphics::Color::from_rgb(80, 150, 250),
+            PickupType::SpecialReset => graphics::Color::from_rgb(50, 210, 220),
+            PickupType::ScoreMultiplier => graphics::Color::from_rgb(230, 90, 230),
+            PickupType::RapidFire => graphics::Color::from_rgb(250, 120, 40),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PickupType::Health => "Health",
+            PickupType::WeaponUpgrade => "Weapon Upgrade",
+            PickupType::Shield => "Shield",
+            PickupType::SpecialReset => "Special Reset",
+            PickupType::ScoreMultiplier => "Score x2",
+            PickupType::RapidFire => "Rapid Fire",
+        }
+    }
+
+    /// How long the effect stays active after pickup in ms, `None` for instant effects
+    pub fn effect_duration(&self) -> Option<f32> {
+        match self {
+            PickupType::Shield => Some(5000.0),
+            PickupType::ScoreMultiplier => Some(10000.0),
+            PickupType::RapidFire => Some(8000.0),
+            _ => None,
+        }
+    }
+}
+
+pub fn roll_pickup_drop(drop_chance: f32) -> Option<PickupType> {
+    let mut rng = rand::thread_rng();
+    if rng.gen_range(0.0..1.0) >= drop_chance {
+        return None;
+    }
+    let pickup_type = match rng.gen_range(0..100) {
+        0..=24 => PickupType::Health,
+        25..=39 => PickupType::WeaponUpgrade,
+        40..=59 => PickupType::Shield,
+        60..=74 => PickupType::SpecialReset,
+        75..=87 => PickupType::ScoreMultiplier,
+        _ => PickupType::RapidFire,
+    };
+    Some(pickup_type)
+}
+
+pub fn create_pickup(x: f32, y: f32, pickup_type: PickupType, mesh: Mesh) -> Actor {
+    Actor {
+        id: next_entity_id(),
+        actor_type: ActorType::Pickup(pickup_type),
+        x,
+        y,
+        // drift down the screen along with the background
+        target_x: x,
+        target_y: y + 10000.0,
+        velocity: PICKUP_DRIFT_VELOCITY,
+        color: pickup_type.color(),
+        hp: PICKUP_LIFETIME,
+        bounding_box: mesh,
+        image: None,
+        is_taking_damage: None,
+        attack_cooldown: None,
+        ai: None,
+        projectile_modifier: None,
+        current_velocity: (0.0, 0.0),
+        steering_goal: None,
+    }
+}
@@ -1,8 +1,16 @@
-use crate::actors::models::{Actor, ActorType};
+use crate::actors::models::{next_entity_id, Actor, ActorType};
 use ggez::graphics::{Color, Image, Mesh};
 
+pub const PLAYER_MAX_HP: f32 = 100.0;
+// dash timings are in ms
+pub const DASH_DURATION: f32 = 150.0;
+pub const DASH_COOLDOWN: f32 = 1500.0;
+pub const DASH_VELOCITY: f32 = 2400.0;
+pub const DASH_TRAIL_LIFETIME: f32 = 200.0;
+
 pub fn create_player(x: f32, y: f32, color: Color, mesh: Mesh, image: Option<Image>) -> Actor {
     Actor {
+        id: next_entity_id(),
         actor_type: ActorType::Player,
         x,
         y,
@@ -10,11 +18,139 @@ pub fn create_player(x: f32, y: f32, color: Color, mesh: Mesh, image: Option<Ima
         target_y: y,
         velocity: 0.1,
         color,
-        hp: 100.0,
+        hp: PLAYER_MAX_HP,
         bounding_box: mesh,
         image,
         is_taking_damage: None,
         attack_cooldown: None,
         ai: None,
+        projectile_modifier: None,
+        current_velocity: (0.0, 0.0),
+        steering_goal: None,
+    }
+}
+
+pub struct PlayerSettings {
+    pub max_hp: f32,
+    pub max_shield: f32,
+    // ms without taking a hit before the shield starts recharging
+    pub shield_recharge_delay: f32,
+    // shield points recovered per second
+    pub shield_recharge_rate: f32,
+    pub lives: u32,
+    // ms of invulnerability after a respawn
+    pub respawn_invulnerability: f32,
+    pub respawn_clear_radius: f32,
+}
+
+pub struct PlayerStatus {
+    pub shield: f32,
+    pub lives: u32,
+    pub shield_recharge_cd: f32,
+    pub invulnerable_cd: f32,
+    pub dash_cd: f32,
+    pub dash_remaining: f32,
+    pub dash_direction: (f32, f32),
+    // (x, y, remaining ms) of the afterimages left behind by a dash
+    pub dash_trail: Vec<(f32, f32, f32)>,
+}
+
+impl PlayerStatus {
+    pub fn new(settings: &PlayerSettings) -> PlayerStatus {
+        PlayerStatus {
+            shield: settings.max_shield,
+            lives: settings.lives,
+            shield_recharge_cd: 0.0,
+            invulnerable_cd: 0.0,
+            dash_cd: 0.0,
+            dash_remaining: 0.0,
+            dash_direction: (0.0, 0.0),
+            dash_trail: vec![],
+        }
+    }
+
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable_cd > 0.0 || self.is_dashing()
+    }
+
+    pub fn is_dashing(&self) -> bool {
+        self.dash_remaining > 0.0
+    }
+
+    /// Starts a dash along `direction` if the cooldown allows it
+    pub fn start_dash(&mut self, direction: (f32, f32)) -> bool {
+        let length = (direction.0.powi(2) + direction.1.powi(2)).sqrt();
+        if self.dash_cd > 0.0 || self.is_dashing() || length <= 0.0 {
+            return false;
+        }
+        self.dash_direction = (direction.0 / length, direction.1 / length);
+        self.dash_remaining = DASH_DURATION;
+        self.dash_cd = DASH_COOLDOWN;
+        true
+    }
+}
+
+pub fn leave_dash_afterimage(status: &mut PlayerStatus, x: f32, y: f32) {
+    status.dash_trail.push((x, y, DASH_TRAIL_LIFETIME));
+}
+
+/// Applies damage to the shield first and any overflow to the player's hp.
+pub fn absorb_damage(
+    player: &mut Actor,
+    status: &mut PlayerStatus,
+    settings: &PlayerSettings,
+    damage: &f32,
+) {
+    if status.is_invulnerable() {
+        return;
+    }
+    let absorbed = status.shield.min(*damage);
+    status.shield -= absorbed;
+    player.hp -= damage - absorbed;
+    status.shield_recharge_cd = settings.shield_recharge_delay;
+}
+
+pub fn handle_player_status(status: &mut PlayerStatus, settings: &PlayerSettings, dt: f32) {
+    if status.dash_cd > 0.0 {
+        status.dash_cd = (status.dash_cd - dt * 1000.0).max(0.0);
+    }
+    for afterimage in &mut status.dash_trail {
+        afterimage.2 -= dt * 1000.0;
+    }
+    status.dash_trail.retain(|afterimage| afterimage.2 > 0.0);
+    if status.invulnerable_cd > 0.0 {
+        status.invulnerable_cd = (status.invulnerable_cd - dt * 1000.0).max(0.0);
+    }
+    if status.shield_recharge_cd > 0.0 {
+        status.shield_recharge_cd = (status.shield_recharge_cd - dt * 1000.0).max(0.0);
+        return;
+    }
+    status.shield = (status.shield + settings.shield_recharge_rate * dt).min(settings.max_shield);
+}
+
+/// Picks the spawn point furthest away from every threat on screen.
+pub fn find_safe_spawn_point(
+    screen_width: f32,
+    screen_height: f32,
+    threats: &[(f32, f32)],
+) -> (f32, f32) {
+    let mut best_point = (screen_width / 2.0, screen_height * 0.8);
+    let mut best_distance = f32::MIN;
+    for column in 1..8 {
+        for row in 3..8 {
+            let point = (
+                screen_width * column as f32 / 8.0,
+                screen_height * row as f32 / 8.0,
+            );
+            let closest_threat = threats
+                .iter()
+                .map(|threat| ((threat.0 - point.0).powi(2) + (threat.1 - point.1).powi(2)).sqrt())
+                .fold(f32::MAX, f32::min);
+            if closest_threat > best_distance {
+                best_distance = closest_threat;
+                best_point = point;
+            }
+        }
     }
+    best_point
 }
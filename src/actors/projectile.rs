@@ -1,7 +1,10 @@
-use crate::actors::models::{Actor, ActorType};
+use crate::actors::models::{next_entity_id, Actor, ActorType};
 use ggez::graphics;
 use ggez::graphics::{Image, Mesh};
 
+pub const PLAYER_PROJECTILE_SPEED: f32 = 800.0;
+pub const ENEMY_PROJECTILE_SPEED: f32 = 800.0;
+
 pub fn handle_timed_life(actor: &mut Actor, dt: f32) {
     actor.hp -= dt;
 }
@@ -17,12 +20,13 @@ pub fn create_player_projectile(
 ) -> Actor {
     let damage = 10.0 * damage_modifier.unwrap_or(1.0);
     Actor {
+        id: next_entity_id(),
         actor_type: ActorType::PlayerProjectile,
         x,
         y,
         target_x,
         target_y,
-        velocity: 800.0,
+        velocity: PLAYER_PROJECTILE_SPEED,
         color: graphics::Color::new(0.8, 0.8, 1.0, 1.0),
         hp: damage,
         bounding_box: mesh,
@@ -30,6 +34,9 @@ pub fn create_player_projectile(
         is_taking_damage: None,
         attack_cooldown: None,
         ai: None,
+        projectile_modifier: None,
+        current_velocity: (0.0, 0.0),
+        steering_goal: None,
     }
 }
 
@@ -44,7 +51,8 @@ pub fn create_player_alt_projectile(
 ) -> Actor {
     let damage = 10.0 * damage_modifier.unwrap_or(1.0);
     Actor {
-        actor_type: ActorType::PlayerProjectile,
+        id: next_entity_id(),
+        actor_type: ActorType::PlayerAltProjectile,
         x,
         y,
         target_x,
@@ -57,6 +65,9 @@ pub fn create_player_alt_projectile(
         is_taking_damage: None,
         attack_cooldown: Some(5000.0),
         ai: None,
+        projectile_modifier: None,
+        current_velocity: (0.0, 0.0),
+        steering_goal: None,
     }
 }
 
@@ -71,12 +82,13 @@ pub fn create_enemy_projectile(
 ) -> Actor {
     let damage = 10.0 * damage_modifier.unwrap_or(1.0);
     Actor {
+        id: next_entity_id(),
         actor_type: ActorType::EnemyProjectile,
         x,
         y,
         target_x,
         target_y,
-        velocity: 800.0,
+        velocity: ENEMY_PROJECTILE_SPEED,
         color: graphics::Color::new(1.0, 0.3, 0.3, 0.8),
         hp: damage,
         bounding_box: mesh,
@@ -84,6 +96,9 @@ pub fn create_enemy_projectile(
         is_taking_damage: None,
         attack_cooldown: None,
         ai: None,
+        projectile_modifier: None,
+        current_velocity: (0.0, 0.0),
+        steering_goal: None,
     }
 }
 
@@ -98,12 +113,13 @@ pub fn create_boss_enemy_projectile(
 ) -> Actor {
     let damage = 5.0 * damage_modifier.unwrap_or(1.0);
     Actor {
+        id: next_entity_id(),
         actor_type: ActorType::EnemyProjectile,
         x,
         y,
         target_x,
         target_y,
-        velocity: 800.0,
+        velocity: ENEMY_PROJECTILE_SPEED,
         color: graphics::Color::new(1.0, 0.3, 0.3, 0.8),
         hp: damage,
         bounding_box: mesh,
@@ -111,5 +127,8 @@ pub fn create_boss_enemy_projectile(
         is_taking_damage: None,
         attack_cooldown: None,
         ai: None,
+        projectile_modifier: None,
+        current_velocity: (0.0, 0.0),
+        steering_goal: None,
     }
 }
@@ -0,0 +1,88 @@
+// ram timings are in ms
+const RAM_WIND_UP: f32 = 600.0;
+const RAM_RECOVERY: f32 = 900.0;
+// px/s during the charge
+const RAM_SPEED: f32 = 1400.0;
+// px the charge carries on past where the target was
+const RAM_OVERSHOOT: f32 = 250.0;
+const RAM_MAX_DISTANCE: f32 = 1400.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RamPhase {
+    // the enemy holds still and telegraphs where it will charge
+    WindUp,
+    Charge,
+    // the enemy drifts to a stop and is open to counterattack
+    Recovery,
+}
+
+/// A charge at high speed along a vector locked in when the ram starts
+#[derive(Debug, Clone, Copy)]
+pub struct RamAttack {
+    pub phase: RamPhase,
+    // unit vector of the charge
+    pub direction: (f32, f32),
+    pub distance: f32,
+    // ms left in the wind-up and recovery, px left during the charge
+    remaining: f32,
+}
+
+impl RamAttack {
+    /// A ram from `origin` through `target`, cut short where it would leave the arena
+    pub fn new(origin: (f32, f32), target: (f32, f32), arena: (f32, f32)) -> RamAttack {
+        let offset = (target.0 - origin.0, target.1 - origin.1);
+        let length = (offset.0.powi(2) + offset.1.powi(2)).sqrt();
+        let direction = match length > 0.0 {
+            true => (offset.0 / length, offset.1 / length),
+            false => (0.0, 1.0),
+        };
+        let mut distance = (length + RAM_OVERSHOOT).min(RAM_MAX_DISTANCE);
+        for (start, step, size) in [
+            (origin.0, direction.0, arena.0),
+            (origin.1, direction.1, arena.1),
+        ] {
+            let to_edge = match step > 0.0 {
+                true => (size - start) / step,
+                false if step < 0.0 => -start / step,
+                false => continue,
+            };
+            distance = distance.min(to_edge.max(0.0));
+        }
+        RamAttack {
+            phase: RamPhase::WindUp,
+            direction,
+            distance,
+            remaining: RAM_WIND_UP,
+        }
+    }
+
+    /// Advances the ram by `dt` ms and returns the velocity to move with, None once it is over
+    pub fn update(&mut self, dt: f32) -> Option<(f32, f32)> {
+        match self.phase {
+            RamPhase::WindUp => {
+                self.remaining -= dt;
+                if self.remaining <= 0.0 {
+                    self.phase = RamPhase::Charge;
+                    self.remaining = self.distance;
+                }
+                Some((0.0, 0.0))
+            }
+            RamPhase::Charge => {
+                self.remaining -= RAM_SPEED * dt / 1000.0;
+                if self.remaining <= 0.0 {
+                    self.phase = RamPhase::Recovery;
+                    self.remaining = RAM_RECOVERY;
+                }
+                Some((self.direction.0 * RAM_SPEED, self.direction.1 * RAM_SPEED))
+            }
+            RamPhase::Recovery => {
+                self.remaining -= dt;
+                if self.remaining <= 0.0 {
+                    return None;
+                }
+                let drift = RAM_SPEED * 0.2 * self.remaining / RAM_RECOVERY;
+                Some((self.direction.0 * drift, self.direction.1 * drift))
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::actors::models::{Actor, ActorType};
+use ggez::mint::Point2;
+use std::f32::consts::PI;
+
+// half of the arc the shield covers, in radians
+const SHIELD_HALF_ARC: f32 = PI / 3.0;
+// radians/s the shield can turn
+const SHIELD_TURN_RATE: f32 = 2.5;
+// px from the enemy the arc is drawn at
+pub const SHIELD_RADIUS: f32 = 40.0;
+// reflected shots hit the player as hard as a basic enemy shot at most
+const REFLECTED_DAMAGE: f32 = 10.0;
+
+/// Chance of a wave enemy carrying a shield, they start showing up from the third wave
+pub fn get_shield_chance(wave_count: f32) -> f32 {
+    match wave_count < 3.0 {
+        true => 0.0,
+        false => (0.05 * (wave_count - 2.0)).min(0.3),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShieldHit {
+    // the shot came in outside the arc
+    Open,
+    // the shot was sent back as an enemy projectile
+    Deflected,
+    // the shot was a special attack and the shield is gone
+    Broken,
+}
+
+/// Armour covering an arc in front of an enemy, the enemy takes full damage from behind
+#[derive(Debug, Clone, Copy)]
+pub struct Shield {
+    // radians, in screen coordinates
+    pub facing: f32,
+}
+
+impl Shield {
+    /// A shield facing from `origin` toward `target`
+    pub fn new(origin: (f32, f32), target: (f32, f32)) -> Shield {
+        Shield {
+            facing: (target.1 - origin.1).atan2(target.0 - origin.0),
+        }
+    }
+
+    /// Turns toward `target` by at most the turn rate, `dt` is in ms
+    pub fn turn_toward(&mut self, origin: (f32, f32), target: (f32, f32), dt: f32) {
+        let wanted = (target.1 - origin.1).atan2(target.0 - origin.0);
+        let max_turn = SHIELD_TURN_RATE * dt / 1000.0;
+        let turn = wrap_angle(wanted - self.facing).clamp(-max_turn, max_turn);
+        self.facing = wrap_angle(self.facing + turn);
+    }
+
+    /// Whether something at `point` is inside the arc the shield covers
+    pub fn covers(&self, origin: (f32, f32), point: (f32, f32)) -> bool {
+        let angle = (point.1 - origin.1).atan2(point.0 - origin.0);
+        wrap_angle(angle - self.facing).abs() <= SHIELD_HALF_ARC
+    }
+
+    /// Resolves a player projectile hitting the enemy at `origin`. Shots inside the arc are
+    /// reflected off the shield back into play as enemy projectiles.
+    pub fn on_hit(&self, origin: (f32, f32), projectile: &mut Actor) -> ShieldHit {
+        if !self.covers(origin, (projectile.x, projectile.y)) {
+            return ShieldHit::Open;
+        }
+        if projectile.actor_type == ActorType::PlayerAltProjectile {
+            return ShieldHit::Broken;
+        }
+        // the shield curves around the enemy, so it faces straight out where the shot lands
+        let offset = (projectile.x - origin.0, projectile.y - origin.1);
+        let distance = (offset.0.powi(2) + offset.1.powi(2)).sqrt();
+        let normal = match distance > 0.0 {
+            true => (offset.0 / distance, offset.1 / distance),
+            false => (self.facing.cos(), self.facing.sin()),
+        };
+        let direction = (
+            projectile.target_x - projectile.x,
+            projectile.target_y - projectile.y,
+        );
+        let length = (direction.0.powi(2) + direction.1.powi(2)).sqrt();
+        let direction = match length > 0.0 {
+            true => (direction.0 / length, direction.1 / length),
+            false => (-normal.0, -normal.1),
+        };
+        let dot = direction.0 * normal.0 + direction.1 * normal.1;
+        // shots grazing the shield from behind its edge are pushed straight out
+        let reflected = match dot < 0.0 {
+            true => (
+                direction.0 - 2.0 * dot * normal.0,
+                direction.1 - 2.0 * dot * normal.1,
+            ),
+            false => normal,
+        };
+        projectile.actor_type = ActorType::EnemyProjectile;
+        projectile.target_x = projectile.x + reflected.0 * 10000.0;
+        projectile.target_y = projectile.y + reflected.1 * 10000.0;
+        projectile.hp = projectile.hp.min(REFLECTED_DAMAGE);
+        ShieldHit::Deflected
+    }
+
+    /// Points along the arc around the enemy at `origin`, for drawing
+    pub fn get_arc_points(&self, origin: (f32, f32)) -> Vec<Point2<f32>> {
+        let segments = 12;
+        (0..=segments)
+            .map(|i| {
+                let angle = self.facing - SHIELD_HALF_ARC
+                    + 2.0 * SHIELD_HALF_ARC * i as f32 / segments as f32;
+                Point2 {
+                    x: origin.0 + angle.cos() * SHIELD_RADIUS,
+                    y: origin.1 + angle.sin() * SHIELD_RADIUS,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Wraps an angle in radians into -PI..PI
+fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(2.0 * PI) - PI
+}
@@ -0,0 +1,259 @@
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Read;
+use thiserror::Error;
+
+// player speeds below this in px/s count as standing still
+const MIN_STRAFE_SPEED: f32 = 50.0;
+
+#[derive(Debug, Error)]
+pub enum AdaptationError {
+    #[error("failed to open adaptation settings: {0}")]
+    Open(#[from] ggez::GameError),
+    #[error("failed to read adaptation settings: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse adaptation settings: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("max_aim_lead can't be negative")]
+    NegativeAimLead,
+    #[error("min_flank_distance is larger than max_flank_distance")]
+    FlankDistanceRange,
+}
+
+/// Designer-set limits for the adaptive enemies, loaded from resources/adaptation.toml
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdaptationSettings {
+    pub enabled: bool,
+    // all durations are in ms
+    pub warm_up: f32,
+    pub smoothing: f32,
+    pub dodge_window: f32,
+    pub volley_weight: f32,
+    pub max_aim_lead: f32,
+    pub min_flank_distance: f32,
+    pub max_flank_distance: f32,
+    // boss pattern name -> alternatives the boss may play in its place
+    #[serde(default)]
+    pub pattern_variants: HashMap<String, Vec<String>>,
+}
+
+pub fn load_adaptation_settings(
+    ctx: &ggez::Context,
+    path: &str,
+) -> Result<AdaptationSettings, AdaptationError> {
+    let mut source = String::new();
+    ctx.fs.open(path)?.read_to_string(&mut source)?;
+    let settings: AdaptationSettings = toml::from_str(&source)?;
+    // the limits are used as clamp bounds, which panic when they are the wrong way round
+    if settings.max_aim_lead < 0.0 {
+        return Err(AdaptationError::NegativeAimLead);
+    }
+    if settings.min_flank_distance > settings.max_flank_distance {
+        return Err(AdaptationError::FlankDistanceRange);
+    }
+    Ok(settings)
+}
+
+struct Volley {
+    // unit vector the shot flew along
+    direction: (f32, f32),
+    player_start: (f32, f32),
+    remaining: f32,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct PatternRecord {
+    plays: u32,
+    damage: f32,
+}
+
+/// Learns the player's habits over a run so enemies can counter them. Sideways movement is
+/// measured across the line from an enemy to the player: positive is to the right as seen
+/// from the enemy, negative to the left.
+pub struct PlayerModel {
+    settings: AdaptationSettings,
+    // cap on how hard the enemies lean on what was learned, set by the difficulty
+    max_strength: f32,
+    observed: f32,
+    // -1 to 1, which way the player tends to circle around the enemies
+    pub strafe_bias: f32,
+    // px/s the player usually moves sideways
+    pub strafe_speed: f32,
+    // px the player usually keeps from the nearest enemy
+    pub typical_distance: f32,
+    // -1 to 1, which side of an incoming volley the player steps to
+    pub dodge_bias: f32,
+    // px the player usually steps aside after a volley
+    pub dodge_distance: f32,
+    volleys: Vec<Volley>,
+    patterns: HashMap<String, PatternRecord>,
+    current_pattern: Option<String>,
+}
+
+impl PlayerModel {
+    pub fn new(settings: AdaptationSettings, max_strength: f32) -> PlayerModel {
+        let typical_distance = (settings.min_flank_distance + settings.max_flank_distance) / 2.0;
+        PlayerModel {
+            settings,
+            max_strength,
+            observed: 0.0,
+            strafe_bias: 0.0,
+            strafe_speed: 0.0,
+            typical_distance,
+            dodge_bias: 0.0,
+            dodge_distance: 0.0,
+            volleys: vec![],
+            patterns: HashMap::new(),
+            current_pattern: None,
+        }
+    }
+
+    /// 0 to `max_strength`, grows as the model sees more of the player
+    pub fn get_strength(&self) -> f32 {
+        match self.settings.enabled {
+            true => self.max_strength * (self.observed / self.settings.warm_up).min(1.0),
+            false => 0.0,
+        }
+    }
+
+    /// Samples the player's movement, `dt` is in ms
+    pub fn record_movement(
+        &mut self,
+        player_position: (f32, f32),
+        player_velocity: (f32, f32),
+        nearest_enemy: Option<(f32, f32)>,
+        dt: f32,
+    ) {
+        self.observed += dt;
+        let weight = (dt / self.settings.smoothing).min(1.0);
+        if let Some(enemy) = nearest_enemy {
+            let offset = (player_position.0 - enemy.0, player_position.1 - enemy.1);
+            let distance = (offset.0.powi(2) + offset.1.powi(2)).sqrt();
+            let speed = (player_velocity.0.powi(2) + player_velocity.1.powi(2)).sqrt();
+            if distance > 0.0 {
+                self.typical_distance += (distance - self.typical_distance) * weight;
+                if speed > MIN_STRAFE_SPEED {
+                    let lateral =
+                        get_lateral((offset.0 / distance, offset.1 / distance), player_velocity);
+                    self.strafe_bias += (lateral / speed - self.strafe_bias) * weight;
+                    self.strafe_speed += (lateral.abs() - self.strafe_speed) * weight;
+                }
+            }
+        }
+
+        // See where the player went once each volley has had time to arrive
+        let volley_weight = self.settings.volley_weight;
+        let mut resolved = vec![];
+        for volley in &mut self.volleys {
+            volley.remaining -= dt;
+            if volley.remaining <= 0.0 {
+                let moved = (
+                    player_position.0 - volley.player_start.0,
+                    player_position.1 - volley.player_start.1,
+                );
+                resolved.push(get_lateral(volley.direction, moved));
+            }
+        }
+        self.volleys.retain(|volley| volley.remaining > 0.0);
+        for lateral in resolved {
+            let side = (lateral / MIN_STRAFE_SPEED).clamp(-1.0, 1.0);
+            self.dodge_bias += (side - self.dodge_bias) * volley_weight;
+            self.dodge_distance += (lateral.abs() - self.dodge_distance) * volley_weight;
+        }
+    }
+
+    /// Remembers a volley fired at the player, to learn how the player reacts to it
+    pub fn record_volley(&mut self, origin: (f32, f32), player_position: (f32, f32)) {
+        let offset = (player_position.0 - origin.0, player_position.1 - origin.1);
+        let distance = (offset.0.powi(2) + offset.1.powi(2)).sqrt();
+        if distance <= 0.0 {
+            return;
+        }
+        self.volleys.push(Volley {
+            direction: (offset.0 / distance, offset.1 / distance),
+            player_start: player_position,
+            remaining: self.settings.dodge_window,
+        });
+    }
+
+    /// How far to shift a shot aimed at the player so it lands where the player usually goes
+    pub fn get_aim_offset(
+        &self,
+        shooter: (f32, f32),
+        player_position: (f32, f32),
+        projectile_speed: f32,
+    ) -> (f32, f32) {
+        let offset = (player_position.0 - shooter.0, player_position.1 - shooter.1);
+        let distance = (offset.0.powi(2) + offset.1.powi(2)).sqrt();
+        if distance <= 0.0 || projectile_speed <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let travel_time = distance / projectile_speed;
+        let lead = self.strafe_bias * self.strafe_speed * travel_time
+            + self.dodge_bias * self.dodge_distance;
+        let lead = (lead * self.get_strength())
+            .clamp(-self.settings.max_aim_lead, self.settings.max_aim_lead);
+        // positive leads go to the right of the line from the shooter to the player
+        (-offset.1 / distance * lead, offset.0 / distance * lead)
+    }
+
+    /// Where enemies without an attack token should wait, moved toward the range the player
+    /// likes to keep so backing off doesn't buy room
+    pub fn get_flank_distance(&self, default: f32) -> f32 {
+        let preferred = self.typical_distance.clamp(
+            self.settings.min_flank_distance,
+            self.settings.max_flank_distance,
+        );
+        default + (preferred - default) * self.get_strength()
+    }
+
+    /// Picks the boss pattern to play in place of `name`. Variants the player hasn't seen are
+    /// tried first, after that the boss favours the one that has hurt the player most.
+    pub fn pick_pattern(&self, name: &str, rng: &mut impl Rng) -> String {
+        let variants = match self.settings.pattern_variants.get(name) {
+            Some(variants) if self.settings.enabled => variants,
+            _ => return name.to_string(),
+        };
+        let candidates: Vec<&str> = std::iter::once(name)
+            .chain(variants.iter().map(|variant| variant.as_str()))
+            .collect();
+        if let Some(unseen) = candidates
+            .iter()
+            .find(|candidate| !self.patterns.contains_key(**candidate))
+        {
+            return unseen.to_string();
+        }
+        match rng.gen::<f32>() < self.get_strength() {
+            true => {
+                let damage_per_play = |candidate: &&str| {
+                    let record = self.patterns.get(*candidate).copied().unwrap_or_default();
+                    record.damage / record.plays.max(1) as f32
+                };
+                candidates
+                    .iter()
+                    .max_by(|a, b| damage_per_play(a).partial_cmp(&damage_per_play(b)).unwrap())
+                    .unwrap_or(&name)
+                    .to_string()
+            }
+            false => candidates[rng.gen_range(0..candidates.len())].to_string(),
+        }
+    }
+
+    pub fn record_pattern(&mut self, name: &str) {
+        self.patterns.entry(name.to_string()).or_default().plays += 1;
+        self.current_pattern = Some(name.to_string());
+    }
+
+    /// Credits damage the player took during a boss fight to the last pattern played
+    pub fn record_pattern_damage(&mut self, amount: f32) {
+        if let Some(name) = &self.current_pattern {
+            self.patterns.entry(name.clone()).or_default().damage += amount;
+        }
+    }
+}
+
+/// Component of `vector` to the right of `direction`, in screen coordinates
+fn get_lateral(direction: (f32, f32), vector: (f32, f32)) -> f32 {
+    direction.0 * vector.1 - direction.1 * vector.0
+}
@@ -1,4 +1,14 @@
+use crate::actors::boss::{load_boss_definitions, BossDefinition};
+use crate::actors::bullet_pattern::{load_bullet_patterns, BulletPattern};
+use crate::actors::enemy::{load_enemy_settings, EnemySettings};
+use crate::adaptation::{load_adaptation_settings, AdaptationSettings};
+use crate::behaviors::enemy_ai::action_timing::{load_action_timings, ActionTimings};
+use crate::behaviors::enemy_ai::hfsm_enemy_ai::{load_hfsm_definitions, HfsmDefinition};
+use crate::behaviors::enemy_ai::perception::{load_perception_profiles, PerceptionProfiles};
+use crate::behaviors::enemy_ai::utility_enemy_ai::{load_utility_config, UtilityConfig};
+use crate::director::{load_director_settings, DirectorSettings};
 use ggez::{audio, graphics, Context, GameResult};
+use std::collections::HashMap;
 
 pub struct Assets {
     pub bgm: audio::Source,
@@ -8,13 +18,23 @@ pub struct Assets {
     pub spread_shot_3: audio::Source,
     pub spread_shot_5: audio::Source,
     pub damage: audio::Source,
+    pub deflect: audio::Source,
     pub background: graphics::Image,
     pub player_ship: graphics::Image,
     pub boss_ship: graphics::Image,
+    pub bullet_patterns: HashMap<String, BulletPattern>,
+    pub boss_definitions: Vec<BossDefinition>,
+    pub enemy_settings: EnemySettings,
+    pub utility_curves: UtilityConfig,
+    pub hfsm_definitions: HashMap<String, HfsmDefinition>,
+    pub action_timings: ActionTimings,
+    pub perception_profiles: PerceptionProfiles,
+    pub director_settings: DirectorSettings,
+    pub adaptation_settings: AdaptationSettings,
 }
 
 impl Assets {
-    pub(crate) fn new(ctx: &mut Context) -> GameResult<Assets> {
+    pub fn new(ctx: &mut Context) -> GameResult<Assets> {
         let bgm = audio::Source::new(ctx, "/Lost in Another World.mp3").expect(
             format!(
                 "Failed to load bgm from path {:?} {:?}",
@@ -71,6 +91,15 @@ impl Assets {
             )
             .as_str(),
         );
+        // the player's laser pitched up, played when a shot glances off a shield
+        let deflect = audio::Source::new(ctx, "/player_laser_1.flac").expect(
+            format!(
+                "Failed to load deflect from path {:?} {:?}",
+                ctx.fs.resources_dir(),
+                "/player_laser_1.flac"
+            )
+            .as_str(),
+        );
         let background = graphics::Image::from_path(ctx, "/background_1.tiff").expect(
             format!(
                 "Failed to load background from path {:?} {:?}",
@@ -95,6 +124,96 @@ impl Assets {
             )
             .as_str(),
         );
+        let bullet_patterns = load_bullet_patterns(ctx, "/bullet_patterns.toml").expect(
+            format!(
+                "Failed to load bullet_patterns from path {:?} {:?}",
+                ctx.fs.resources_dir(),
+                "/bullet_patterns.toml"
+            )
+            .as_str(),
+        );
+        let boss_definitions = load_boss_definitions(ctx, "/bosses.toml").expect(
+            format!(
+                "Failed to load boss_definitions from path {:?} {:?}",
+                ctx.fs.resources_dir(),
+                "/bosses.toml"
+            )
+            .as_str(),
+        );
+        // Phases refer to patterns by name, catch typos here instead of a phase that never fires
+        for boss in &boss_definitions {
+            for phase in &boss.phases {
+                if !bullet_patterns.contains_key(&phase.pattern) {
+                    panic!(
+                        "Boss {:?} uses unknown bullet pattern {:?}",
+                        boss.name, phase.pattern
+                    );
+                }
+            }
+        }
+        let enemy_settings = load_enemy_settings(ctx, "/enemies.toml").expect(
+            format!(
+                "Failed to load enemy_settings from path {:?} {:?}",
+                ctx.fs.resources_dir(),
+                "/enemies.toml"
+            )
+            .as_str(),
+        );
+        let utility_curves = load_utility_config(ctx, "/utility_curves.toml").expect(
+            format!(
+                "Failed to load utility_curves from path {:?} {:?}",
+                ctx.fs.resources_dir(),
+                "/utility_curves.toml"
+            )
+            .as_str(),
+        );
+        let hfsm_definitions = load_hfsm_definitions(ctx, "/hfsm_enemies.toml").expect(
+            format!(
+                "Failed to load hfsm_definitions from path {:?} {:?}",
+                ctx.fs.resources_dir(),
+                "/hfsm_enemies.toml"
+            )
+            .as_str(),
+        );
+        let action_timings = load_action_timings(ctx, "/action_timings.toml").expect(
+            format!(
+                "Failed to load action_timings from path {:?} {:?}",
+                ctx.fs.resources_dir(),
+                "/action_timings.toml"
+            )
+            .as_str(),
+        );
+        let perception_profiles = load_perception_profiles(ctx, "/perception.toml").expect(
+            format!(
+                "Failed to load perception_profiles from path {:?} {:?}",
+                ctx.fs.resources_dir(),
+                "/perception.toml"
+            )
+            .as_str(),
+        );
+        let director_settings = load_director_settings(ctx, "/director.toml").expect(
+            format!(
+                "Failed to load director_settings from path {:?} {:?}",
+                ctx.fs.resources_dir(),
+                "/director.toml"
+            )
+            .as_str(),
+        );
+        let adaptation_settings = load_adaptation_settings(ctx, "/adaptation.toml").expect(
+            format!(
+                "Failed to load adaptation_settings from path {:?} {:?}",
+                ctx.fs.resources_dir(),
+                "/adaptation.toml"
+            )
+            .as_str(),
+        );
+        for (name, variants) in &adaptation_settings.pattern_variants {
+            for pattern in std::iter::once(name).chain(variants) {
+                if !bullet_patterns.contains_key(pattern) {
+                    panic!("Unknown bullet pattern {:?} in pattern_variants", pattern);
+                }
+            }
+        }
         Ok(Assets {
             bgm,
             player_laser_1,
@@ -103,9 +222,19 @@ impl Assets {
             spread_shot_3,
             spread_shot_5,
             damage,
+            deflect,
             background,
             player_ship,
             boss_ship,
+            bullet_patterns,
+            boss_definitions,
+            enemy_settings,
+            utility_curves,
+            hfsm_definitions,
+            action_timings,
+            perception_profiles,
+            director_settings,
+            adaptation_settings,
         })
     }
 }
@@ -0,0 +1,311 @@
+use crate::behaviors::enemy_ai::world_view::{Faction, ProjectileView};
+use ggez::event::MouseButton;
+use ggez::input::keyboard::KeyCode;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
+
+// distance the bot tries to keep from the enemy it is shooting at
+const PREFERRED_RANGE: f32 = 450.0;
+// how close to the screen edge the bot gets before turning back
+const EDGE_MARGIN: f32 = 120.0;
+const MOVEMENT_KEYS: [KeyCode; 4] = [KeyCode::W, KeyCode::A, KeyCode::S, KeyCode::D];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AutopilotSkill {
+    Novice,
+    Average,
+    Expert,
+}
+
+impl AutopilotSkill {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AutopilotSkill::Novice => "Novice",
+            AutopilotSkill::Average => "Average",
+            AutopilotSkill::Expert => "Expert",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<AutopilotSkill> {
+        match name.to_lowercase().as_str() {
+            "novice" => Some(AutopilotSkill::Novice),
+            "average" => Some(AutopilotSkill::Average),
+            "expert" => Some(AutopilotSkill::Expert),
+            _ => None,
+        }
+    }
+
+    fn settings(&self) -> AutopilotSettings {
+        match self {
+            AutopilotSkill::Novice => AutopilotSettings {
+                reaction_time: 350.0,
+                dodge_radius: 90.0,
+                lookahead: 400.0,
+                aim_error: 80.0,
+                fire_interval: 450.0,
+                is_leading_shots: false,
+                is_dashing: false,
+            },
+            AutopilotSkill::Average => AutopilotSettings {
+                reaction_time: 180.0,
+                dodge_radius: 130.0,
+                lookahead: 700.0,
+                aim_error: 35.0,
+                fire_interval: 250.0,
+                is_leading_shots: true,
+                is_dashing: false,
+            },
+            AutopilotSkill::Expert => AutopilotSettings {
+                reaction_time: 60.0,
+                dodge_radius: 170.0,
+                lookahead: 1000.0,
+                aim_error: 10.0,
+                fire_interval: 150.0,
+                is_leading_shots: true,
+                is_dashing: true,
+            },
+        }
+    }
+}
+
+struct AutopilotSettings {
+    // all durations are in ms
+    reaction_time: f32,
+    dodge_radius: f32,
+    // how far ahead incoming projectiles are traced
+    lookahead: f32,
+    // random offset in px added to every aim point
+    aim_error: f32,
+    fire_interval: f32,
+    is_leading_shots: bool,
+    is_dashing: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TargetView {
+    pub position: (f32, f32),
+    pub velocity: (f32, f32),
+}
+
+/// What the bot can see of the game each frame
+pub struct AutopilotView<'a> {
+    pub player: (f32, f32),
+    pub enemies: &'a [TargetView],
+    pub projectiles: &'a [ProjectileView],
+    pub player_projectile_speed: f32,
+    pub alt_cd: f32,
+    pub dash_key: KeyCode,
+    pub arena: (f32, f32),
+}
+
+/// Input events for the game to replay through its normal event handlers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BotInput {
+    KeyDown(KeyCode),
+    KeyUp(KeyCode),
+    MouseDown(MouseButton, f32, f32),
+}
+
+/// Plays the game for soak tests, balance sweeps and attract mode. It dodges enemy projectiles,
+/// strafes around the nearest enemy while shooting at it and fires the special attack whenever
+/// it is ready.
+pub struct Autopilot {
+    pub skill: AutopilotSkill,
+    settings: AutopilotSettings,
+    rng: StdRng,
+    held_keys: HashSet<KeyCode>,
+    reaction_cd: f32,
+    fire_cd: f32,
+    strafe_direction: f32,
+    strafe_cd: f32,
+}
+
+impl Autopilot {
+    pub fn new(skill: AutopilotSkill, seed: u64) -> Autopilot {
+        Autopilot {
+            skill,
+            settings: skill.settings(),
+            rng: StdRng::seed_from_u64(seed),
+            held_keys: HashSet::new(),
+            reaction_cd: 0.0,
+            fire_cd: 0.0,
+            strafe_direction: 1.0,
+            strafe_cd: 0.0,
+        }
+    }
+
+    /// Releases every key the bot is holding, used when it hands control back to the player
+    pub fn release_keys(&mut self) -> Vec<BotInput> {
+        self.held_keys.drain().map(BotInput::KeyUp).collect()
+    }
+
+    /// Input for this frame, `dt` is in ms
+    pub fn get_input(&mut self, view: &AutopilotView, dt: f32) -> Vec<BotInput> {
+        self.fire_cd -= dt;
+        self.strafe_cd -= dt;
+        self.reaction_cd -= dt;
+        if self.strafe_cd <= 0.0 {
+            self.strafe_cd = self.rng.gen_range(1200.0..3500.0);
+            if self.rng.gen_bool(0.5) {
+                self.strafe_direction = -self.strafe_direction;
+            }
+        }
+        let mut inputs = vec![];
+        // Like a person, the bot only changes course every so often
+        if self.reaction_cd <= 0.0 {
+            self.reaction_cd = self.settings.reaction_time;
+            let (direction, time_to_impact) = self.get_movement(view);
+            let keys = get_movement_keys(direction);
+            for key in MOVEMENT_KEYS {
+                match (keys.contains(&key), self.held_keys.contains(&key)) {
+                    (true, false) => {
+                        self.held_keys.insert(key);
+                        inputs.push(BotInput::KeyDown(key));
+                    }
+                    (false, true) => {
+                        self.held_keys.remove(&key);
+                        inputs.push(BotInput::KeyUp(key));
+                    }
+                    _ => (),
+                }
+            }
+            let is_hit_imminent = time_to_impact.is_some_and(|time| time < 200.0);
+            if self.settings.is_dashing && is_hit_imminent && !keys.is_empty() {
+                inputs.push(BotInput::KeyDown(view.dash_key));
+            }
+        }
+
+        if let Some(target) = self.get_aim_point(view) {
+            if self.fire_cd <= 0.0 {
+                self.fire_cd = self.settings.fire_interval;
+                inputs.push(BotInput::MouseDown(MouseButton::Left, target.0, target.1));
+            }
+            if view.alt_cd <= 0.0 {
+                inputs.push(BotInput::MouseDown(MouseButton::Right, target.0, target.1));
+            }
+        }
+        inputs
+    }
+
+    /// Direction to move in, and how soon the most urgent projectile would hit
+    fn get_movement(&self, view: &AutopilotView) -> ((f32, f32), Option<f32>) {
+        let position = view.player;
+        let mut direction = (0.0, 0.0);
+        let mut time_to_impact: Option<f32> = None;
+        for projectile in view.projectiles {
+            if projectile.faction != Faction::Enemy {
+                continue;
+            }
+            let speed = (projectile.velocity.0.powi(2) + projectile.velocity.1.powi(2)).sqrt();
+            if speed <= 0.0 {
+                continue;
+            }
+            // closest point of the projectile's path to the player, and when it gets there
+            let offset = (
+                position.0 - projectile.position.0,
+                position.1 - projectile.position.1,
+            );
+            let time = (offset.0 * projectile.velocity.0 + offset.1 * projectile.velocity.1)
+                / speed.powi(2)
+                * 1000.0;
+            if time < 0.0 || time > self.settings.lookahead {
+                continue;
+            }
+            let closest = (
+                projectile.position.0 + projectile.velocity.0 * time / 1000.0,
+                projectile.position.1 + projectile.velocity.1 * time / 1000.0,
+            );
+            let miss = (position.0 - closest.0, position.1 - closest.1);
+            let miss_distance = (miss.0.powi(2) + miss.1.powi(2)).sqrt();
+            if miss_distance > self.settings.dodge_radius {
+                continue;
+            }
+            // step out of the path on whichever side we are already on, sooner hits count more
+            let side = match miss_distance > 0.0 {
+                true => (miss.0 / miss_distance, miss.1 / miss_distance),
+                false => (
+                    -projectile.velocity.1 / speed,
+                    projectile.velocity.0 / speed,
+                ),
+            };
+            let urgency = 1.0 - time / self.settings.lookahead + 0.5;
+            direction.0 += side.0 * urgency * 3.0;
+            direction.1 += side.1 * urgency * 3.0;
+            time_to_impact = Some(time_to_impact.map_or(time, |other| other.min(time)));
+        }
+
+        if let Some((enemy, distance)) = get_nearest_enemy(view) {
+            if distance > 0.0 {
+                let toward = (
+                    (enemy.position.0 - position.0) / distance,
+                    (enemy.position.1 - position.1) / distance,
+                );
+                let range_error = ((distance - PREFERRED_RANGE) / PREFERRED_RANGE).clamp(-1.0, 1.0);
+                direction.0 += toward.0 * range_error - toward.1 * self.strafe_direction;
+                direction.1 += toward.1 * range_error + toward.0 * self.strafe_direction;
+            }
+        }
+
+        // Turn back before getting pinned against the edge of the screen
+        if position.0 < EDGE_MARGIN {
+            direction.0 += 2.0;
+        } else if position.0 > view.arena.0 - EDGE_MARGIN {
+            direction.0 -= 2.0;
+        }
+        if position.1 < EDGE_MARGIN {
+            direction.1 += 2.0;
+        } else if position.1 > view.arena.1 - EDGE_MARGIN {
+            direction.1 -= 2.0;
+        }
+        (direction, time_to_impact)
+    }
+
+    fn get_aim_point(&mut self, view: &AutopilotView) -> Option<(f32, f32)> {
+        let (enemy, distance) = get_nearest_enemy(view)?;
+        let mut target = enemy.position;
+        if self.settings.is_leading_shots && view.player_projectile_speed > 0.0 {
+            let travel_time = distance / view.player_projectile_speed;
+            target.0 += enemy.velocity.0 * travel_time;
+            target.1 += enemy.velocity.1 * travel_time;
+        }
+        let error = self.settings.aim_error;
+        Some((
+            target.0 + self.rng.gen_range(-error..=error),
+            target.1 + self.rng.gen_range(-error..=error),
+        ))
+    }
+}
+
+fn get_nearest_enemy(view: &AutopilotView) -> Option<(TargetView, f32)> {
+    view.enemies
+        .iter()
+        .map(|enemy| {
+            let distance = ((enemy.position.0 - view.player.0).powi(2)
+                + (enemy.position.1 - view.player.1).powi(2))
+            .sqrt();
+            (*enemy, distance)
+        })
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+}
+
+fn get_movement_keys(direction: (f32, f32)) -> HashSet<KeyCode> {
+    let mut keys = HashSet::new();
+    let length = (direction.0.powi(2) + direction.1.powi(2)).sqrt();
+    if length < 0.1 {
+        return keys;
+    }
+    // Only press a key when that axis is a real part of the direction
+    let direction = (direction.0 / length, direction.1 / length);
+    if direction.0 > 0.35 {
+        keys.insert(KeyCode::D);
+    } else if direction.0 < -0.35 {
+        keys.insert(KeyCode::A);
+    }
+    if direction.1 > 0.35 {
+        keys.insert(KeyCode::S);
+    } else if direction.1 < -0.35 {
+        keys.insert(KeyCode::W);
+    }
+    keys
+}
@@ -1,6 +1,6 @@
 use crate::behaviors::model::{Behavior, BehaviorTreeTrait, EnemyBehaviors, Node, NodeTrait};
 
-fn get_aggressive_enemy_behaviors() -> EnemyBehaviors {
+pub(crate) fn get_aggressive_enemy_behaviors() -> EnemyBehaviors {
     EnemyBehaviors {
         root: Node::new(0, Behavior::Idle, None, Some((1, 2))),
         nodes: vec![
@@ -87,4 +87,8 @@ impl BehaviorTreeTrait for AggressiveEnemyBehaviorTree {
     fn get_current_node(&self) -> u32 {
         self.current_node
     }
+
+    fn set_current_node(&mut self, node_id: u32) {
+        self.current_node = node_id;
+    }
 }
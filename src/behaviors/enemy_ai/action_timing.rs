@@ -0,0 +1,295 @@
+use crate::behaviors::enemy_ai::debugger::TraceLog;
+use crate::behaviors::enemy_ai::world_view::{AiCommand, WorldView};
+use crate::behaviors::model::Behavior;
+use crate::behaviors::steering::SteeringGoal;
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+#[cfg(feature = "game")]
+use std::io::Read;
+use thiserror::Error;
+
+const DEFAULT_ACTION_TIMINGS: &str = include_str!("../../../resources/action_timings.toml");
+// how long behaviors missing from a profile run, matching the old fixed gate
+const DEFAULT_DURATION: f32 = 800.0;
+
+#[derive(Debug, Error)]
+pub enum ActionTimingError {
+    #[cfg(feature = "game")]
+    #[error("failed to open action timings: {0}")]
+    Open(#[from] ggez::GameError),
+    #[error("failed to read action timings: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse action timings: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("behavior {1:?} in profile `{0}` has a min_duration above its max_duration")]
+    InvalidDuration(String, Behavior),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ActionTimings {
+    pub profiles: HashMap<String, TimingProfile>,
+}
+
+impl ActionTimings {
+    /// The named profile, or an empty one where every behavior uses the default timing
+    pub fn get_profile(&self, name: &str) -> TimingProfile {
+        self.profiles.get(name).cloned().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TimingProfile {
+    #[serde(default)]
+    pub behaviors: Vec<BehaviorTiming>,
+    #[serde(default)]
+    pub interrupts: Vec<InterruptRule>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BehaviorTiming {
+    pub behavior: Behavior,
+    // ms, the actual duration is picked at random in this range
+    pub min_duration: f32,
+    pub max_duration: f32,
+    // ms between re-issuing the behavior's commands while it runs
+    pub repeat_interval: Option<f32>,
+    #[serde(default)]
+    pub completion: Completion,
+    // interrupts only preempt behaviors with a lower priority
+    #[serde(default)]
+    pub priority: u32,
+}
+
+/// Lets a behavior finish before its duration is up, once it has run for `min_duration`
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Completion {
+    #[default]
+    Timer,
+    // within `radius` of the point the behavior moved to
+    Arrived {
+        radius: f32,
+    },
+    // after firing `shots` times
+    Volley {
+        shots: u32,
+    },
+    // no player projectiles within `radius`
+    Clear {
+        radius: f32,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InterruptRule {
+    pub behavior: Behavior,
+    pub trigger: InterruptTrigger,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InterruptTrigger {
+    ProjectileWithin { distance: f32 },
+    PlayerWithin { distance: f32 },
+}
+
+impl InterruptTrigger {
+    fn is_triggered(&self, world: &WorldView) -> bool {
+        match self {
+            InterruptTrigger::ProjectileWithin { distance } => {
+                get_closest_projectile_distance(world) <= *distance
+            }
+            InterruptTrigger::PlayerWithin { distance } => {
+                world.get_distance_to_player() <= *distance
+            }
+        }
+    }
+}
+
+fn get_closest_projectile_distance(world: &WorldView) -> f32 {
+    let position = world.actor.position;
+    world
+        .get_hostile_projectile_positions()
+        .iter()
+        .map(|projectile| {
+            ((projectile.0 - position.0).powi(2) + (projectile.1 - position.1).powi(2)).sqrt()
+        })
+        .fold(f32::MAX, f32::min)
+}
+
+pub fn parse_action_timings(source: &str) -> Result<ActionTimings, ActionTimingError> {
+    let timings: ActionTimings = toml::from_str(source)?;
+    for (name, profile) in &timings.profiles {
+        for timing in &profile.behaviors {
+            if timing.min_duration > timing.max_duration {
+                return Err(ActionTimingError::InvalidDuration(
+                    name.clone(),
+                    timing.behavior.clone(),
+                ));
+            }
+        }
+    }
+    Ok(timings)
+}
+
+#[cfg(feature = "game")]
+pub fn load_action_timings(
+    ctx: &ggez::Context,
+    path: &str,
+) -> Result<ActionTimings, ActionTimingError> {
+    let mut source = String::new();
+    ctx.fs.open(path)?.read_to_string(&mut source)?;
+    parse_action_timings(&source)
+}
+
+/// The bundled profile, for AIs built without the loaded assets
+pub fn get_default_profile(name: &str) -> TimingProfile {
+    parse_action_timings(DEFAULT_ACTION_TIMINGS)
+        .expect("Failed to parse the bundled action timings")
+        .get_profile(name)
+}
+
+struct RunningAction {
+    behavior: Behavior,
+    // None when the behavior interrupted the tree
+    node_id: Option<u32>,
+    started_at: u128,
+    last_issued: u128,
+    duration: f32,
+    shots_fired: u32,
+    move_target: Option<(f32, f32)>,
+}
+
+/// Keeps a behavior running for its duration and decides when it is done or preempted
+pub struct ActionRunner {
+    profile: TimingProfile,
+    running: Option<RunningAction>,
+    trace: TraceLog,
+}
+
+impl ActionRunner {
+    pub fn new(profile: TimingProfile) -> ActionRunner {
+        ActionRunner {
+            profile,
+            running: None,
+            trace: TraceLog::default(),
+        }
+    }
+
+    fn get_timing(&self, behavior: &Behavior) -> BehaviorTiming {
+        self.profile
+            .behaviors
+            .iter()
+            .find(|timing| &timing.behavior == behavior)
+            .cloned()
+            .unwrap_or(BehaviorTiming {
+                behavior: behavior.clone(),
+                min_duration: DEFAULT_DURATION,
+                max_duration: DEFAULT_DURATION,
+                repeat_interval: None,
+                completion: Completion::Timer,
+                priority: 0,
+            })
+    }
+
+    pub fn get_behavior(&self) -> Option<Behavior> {
+        self.running
+            .as_ref()
+            .map(|running| running.behavior.clone())
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.running
+            .as_ref()
+            .is_some_and(|running| running.node_id.is_none())
+    }
+
+    pub fn get_trace(&self) -> &TraceLog {
+        &self.trace
+    }
+
+    /// An interrupt behavior whose trigger fired and that outranks the running behavior
+    pub fn get_interrupt(&self, world: &WorldView) -> Option<Behavior> {
+        let running_priority = match &self.running {
+            Some(running) => self.get_timing(&running.behavior).priority,
+            None => return None,
+        };
+        self.profile
+            .interrupts
+            .iter()
+            .filter(|rule| self.get_timing(&rule.behavior).priority > running_priority)
+            .find(|rule| rule.trigger.is_triggered(world))
+            .map(|rule| rule.behavior.clone())
+    }
+
+    pub fn is_finished(&self, world: &WorldView) -> bool {
+        let running = match &self.running {
+            Some(running) => running,
+            None => return true,
+        };
+        let elapsed = (world.current_time - running.started_at) as f32;
+        if elapsed >= running.duration {
+            return true;
+        }
+        let timing = self.get_timing(&running.behavior);
+        if elapsed < timing.min_duration {
+            return false;
+        }
+        match timing.completion {
+            Completion::Timer => false,
+            Completion::Arrived { radius } => running.move_target.is_some_and(|target| {
+                let position = world.actor.position;
+                ((target.0 - position.0).powi(2) + (target.1 - position.1).powi(2)).sqrt() <= radius
+            }),
+            Completion::Volley { shots } => running.shots_fired >= shots,
+            Completion::Clear { radius } => get_closest_projectile_distance(world) > radius,
+        }
+    }
+
+    /// Whether the running behavior should issue its commands again this frame
+    pub fn is_repeat_due(&self, current_time: u128) -> bool {
+        match &self.running {
+            Some(running) => self
+                .get_timing(&running.behavior)
+                .repeat_interval
+                .is_some_and(|interval| (current_time - running.last_issued) as f32 >= interval),
+            None => false,
+        }
+    }
+
+    pub fn start(&mut self, behavior: Behavior, node_id: Option<u32>, current_time: u128) {
+        self.trace.record(current_time, node_id, behavior.clone());
+        let timing = self.get_timing(&behavior);
+        let duration = match timing.max_duration > timing.min_duration {
+            true => rand::thread_rng().gen_range(timing.min_duration..timing.max_duration),
+            false => timing.max_duration,
+        };
+        self.running = Some(RunningAction {
+            behavior,
+            node_id,
+            started_at: current_time,
+            last_issued: current_time,
+            duration,
+            shots_fired: 0,
+            move_target: None,
+        });
+    }
+
+    /// Tracks what the running behavior asked for, to tell when it has completed
+    pub fn record(&mut self, current_time: u128, commands: &[AiCommand]) {
+        if let Some(running) = &mut self.running {
+            running.last_issued = current_time;
+            for command in commands {
+                match command {
+                    AiCommand::Fire(_) => running.shots_fired += 1,
+                    AiCommand::Move(SteeringGoal::Arrive(target))
+                    | AiCommand::Move(SteeringGoal::Seek(target)) => {
+                        running.move_target = Some(*target)
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
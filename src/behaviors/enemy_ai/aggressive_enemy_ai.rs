@@ -1,8 +1,12 @@
 use crate::behaviors::aggressive_enemy_behavior_tree::AggressiveEnemyBehaviorTree;
+use crate::behaviors::enemy_ai::action_timing::{get_default_profile, ActionRunner, TimingProfile};
+use crate::behaviors::enemy_ai::debugger::{get_tree_nodes, AiDebugInfo, TraceLog};
 use crate::behaviors::enemy_ai::model::{
-    calculate_dodge_position, get_next_child_actions, ActionResult, BehaviorAction, EnemyAi,
+    calculate_dodge_position, get_next_child_actions, BehaviorAction, EnemyAi,
 };
+use crate::behaviors::enemy_ai::world_view::{AiCommand, SoundCue, WeaponId, WorldView};
 use crate::behaviors::model::{Behavior, BehaviorTreeTrait, NodeTrait};
+use crate::behaviors::steering::{SteeringGoal, SteeringWeights};
 use anyhow::Result;
 use rand::Rng;
 use std::collections::BinaryHeap;
@@ -11,10 +15,11 @@ pub struct AggressiveEnemyAI {
     behavior_tree: AggressiveEnemyBehaviorTree,
     current_action: BehaviorAction,
     action_heap: BinaryHeap<BehaviorAction>,
+    action_runner: ActionRunner,
 }
 
-impl EnemyAi for AggressiveEnemyAI {
-    fn new() -> Self {
+impl AggressiveEnemyAI {
+    pub fn from_timing(timing: TimingProfile) -> AggressiveEnemyAI {
         let behavior_tree = AggressiveEnemyBehaviorTree::new();
         let current_action = BehaviorAction {
             behavior: behavior_tree.get_root().get_name(),
@@ -36,60 +41,65 @@ impl EnemyAi for AggressiveEnemyAI {
             behavior_tree,
             current_action,
             action_heap,
+            action_runner: ActionRunner::new(timing),
         }
     }
 
-    fn perform_action(
-        &mut self,
-        current_time: u128,
-        player_position: (f32, f32),
-        enemy_position: (f32, f32),
-        speed: f32,
-        projectile_positions: Vec<(f32, f32)>,
-    ) -> Result<ActionResult> {
-        let mut result = ActionResult {
-            enemy_position,
-            enemy_target: player_position,
-            is_attacking: false,
-        };
-        if current_time - self.current_action.last_performed < 800 {
-            return Ok(result);
-        };
-        // Perform the action
-        match self.current_action.behavior {
+    fn get_commands(&self, behavior: &Behavior, world: &WorldView) -> Vec<AiCommand> {
+        let player_position = world.player.position;
+        let enemy_position = world.actor.position;
+        let mut commands = vec![];
+        match behavior {
             Behavior::Idle => {
-                result.enemy_position = enemy_position;
+                commands.push(AiCommand::Move(SteeringGoal::Hold));
             }
             Behavior::MoveToRandom => {
                 let mut rng = rand::thread_rng();
                 let x = rng.gen_range(
-                    (enemy_position.0 - 1000.0).min(0.0)..(enemy_position.0 + 1000.0).max(1920.0),
+                    (enemy_position.0 - 1000.0).min(0.0)
+                        ..(enemy_position.0 + 1000.0).max(world.arena.0),
                 );
                 let y = rng.gen_range(
-                    (enemy_position.1 - 1000.0).min(0.0)..(enemy_position.1 + 1000.0).max(1080.0),
+                    (enemy_position.1 - 1000.0).min(0.0)
+                        ..(enemy_position.1 + 1000.0).max(world.arena.1),
                 );
-                result.enemy_position = (x, y);
+                commands.push(AiCommand::Move(SteeringGoal::Arrive((x, y))));
             }
             Behavior::MoveToPlayer => {
-                let x = player_position.0;
-                let y = player_position.1;
-                result.enemy_position = (x, y);
+                commands.push(AiCommand::Move(SteeringGoal::PursuePlayer));
             }
             Behavior::AttackPlayer => {
                 let x = player_position.0;
                 let y = player_position.1;
-                result.enemy_target = (x, y);
-                result.is_attacking = true;
+                commands.push(AiCommand::Aim((x, y)));
+                // Up close the spread shot is hard to slip past
+                if world.get_distance_to_player() < 400.0 {
+                    commands.push(AiCommand::Sound(SoundCue::Charge));
+                    commands.push(AiCommand::Fire(WeaponId::Spread));
+                } else {
+                    commands.push(AiCommand::Fire(WeaponId::Blaster));
+                }
             }
             Behavior::Dodge => {
-                result.enemy_position =
-                    calculate_dodge_position(enemy_position, projectile_positions, speed);
+                commands.push(AiCommand::Move(SteeringGoal::Seek(
+                    calculate_dodge_position(
+                        enemy_position,
+                        world.get_hostile_projectile_positions(),
+                        world.hazards,
+                        world.actor.max_speed,
+                        &mut rand::thread_rng(),
+                    ),
+                )));
             }
             _ => {
-                result.enemy_position = enemy_position;
+                commands.push(AiCommand::Move(SteeringGoal::Hold));
             }
         }
+        commands
+    }
 
+    /// Moves on to the next action in the tree once the running one is done
+    fn advance(&mut self, current_time: u128) {
         if self
             .behavior_tree
             .get_node_children(self.current_action.node_id)
@@ -114,7 +124,66 @@ impl EnemyAi for AggressiveEnemyAI {
             node_id: 0,
             last_performed: current_time,
         });
+    }
+}
+
+impl EnemyAi for AggressiveEnemyAI {
+    fn new() -> Self {
+        AggressiveEnemyAI::from_timing(get_default_profile("aggressive"))
+    }
+
+    fn perform_action(&mut self, world: &WorldView) -> Result<Vec<AiCommand>> {
+        let current_time = world.current_time;
+        let behavior = match self.action_runner.get_interrupt(world) {
+            Some(interrupt) => {
+                self.action_runner
+                    .start(interrupt.clone(), None, current_time);
+                interrupt
+            }
+            None if self.action_runner.is_finished(world) => {
+                let behavior = self.current_action.behavior.clone();
+                let node_id = self.current_action.node_id;
+                self.behavior_tree.set_current_node(node_id);
+                self.action_runner
+                    .start(behavior.clone(), Some(node_id), current_time);
+                self.advance(current_time);
+                behavior
+            }
+            None if self.action_runner.is_repeat_due(current_time) => {
+                self.action_runner.get_behavior().unwrap_or(Behavior::Idle)
+            }
+            None => return Ok(vec![]),
+        };
+        let commands = self.get_commands(&behavior, world);
+        self.action_runner.record(current_time, &commands);
+        Ok(commands)
+    }
+
+    fn get_debug_info(&self) -> Option<AiDebugInfo> {
+        Some(AiDebugInfo {
+            behavior: self.action_runner.get_behavior()?,
+            node_id: match self.action_runner.is_interrupted() {
+                true => None,
+                false => Some(self.behavior_tree.get_current_node()),
+            },
+            tree: get_tree_nodes(&self.behavior_tree),
+        })
+    }
 
-        return Ok(result);
+    fn get_trace(&self) -> Option<&TraceLog> {
+        Some(self.action_runner.get_trace())
+    }
+
+    fn get_steering_weights(&self) -> SteeringWeights {
+        SteeringWeights {
+            goal: 1.4,
+            separation: 1.0,
+            cohesion: 0.1,
+            alignment: 0.2,
+            wander: 0.05,
+            max_acceleration: 1800.0,
+            turn_rate: 6.0,
+            ..SteeringWeights::default()
+        }
     }
 }
@@ -0,0 +1,62 @@
+use crate::behaviors::enemy_ai::model::{perform_behavior, EnemyAi};
+use crate::behaviors::enemy_ai::world_view::{AiCommand, WorldView};
+use crate::behaviors::tree_definition::{get_builtin_trees, TreeDefinition};
+use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// ms each node's behavior runs before moving down the tree
+const NODE_DURATION: u128 = 800;
+
+/// Walks a behavior tree loaded as data: each node's behavior runs for a while, then a random
+/// child takes over, and leaves go back to the root. Seeded so runs can be replayed.
+pub struct DataTreeEnemyAI {
+    tree: TreeDefinition,
+    rng: StdRng,
+    // index into `tree.nodes`
+    current: usize,
+    last_performed: Option<u128>,
+}
+
+impl DataTreeEnemyAI {
+    /// The tree must be valid, see `TreeDefinition::validate`
+    pub fn from_definition(tree: TreeDefinition, seed: u64) -> DataTreeEnemyAI {
+        DataTreeEnemyAI {
+            tree,
+            rng: StdRng::seed_from_u64(seed),
+            current: 0,
+            last_performed: None,
+        }
+    }
+
+    fn advance(&mut self) {
+        let children = self.tree.nodes[self.current].children;
+        let next = children.and_then(|(left, right)| {
+            let child = match self.rng.gen_bool(0.5) {
+                true => left,
+                false => right,
+            };
+            self.tree.nodes.iter().position(|node| node.id == child)
+        });
+        self.current = next.unwrap_or(0);
+    }
+}
+
+impl EnemyAi for DataTreeEnemyAI {
+    fn new() -> Self {
+        let tree = get_builtin_trees().remove(0);
+        DataTreeEnemyAI::from_definition(tree, rand::thread_rng().gen())
+    }
+
+    fn perform_action(&mut self, world: &WorldView) -> Result<Vec<AiCommand>> {
+        let current_time = world.current_time;
+        match self.last_performed {
+            Some(last) if current_time - last < NODE_DURATION => return Ok(vec![]),
+            Some(_) => self.advance(),
+            None => (),
+        }
+        self.last_performed = Some(current_time);
+        let behavior = self.tree.nodes[self.current].behavior.clone();
+        Ok(perform_behavior(&behavior, world, &mut self.rng))
+    }
+}
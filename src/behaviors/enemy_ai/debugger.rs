@@ -0,0 +1,85 @@
+use crate::behaviors::model::{Behavior, BehaviorTreeTrait, Node, NodeTrait};
+use crate::entity::EntityId;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+
+// oldest transitions are dropped past this many per enemy
+const MAX_TRACE_ENTRIES: usize = 200;
+
+/// What an AI is running right now, for the debug overlay
+pub struct AiDebugInfo {
+    pub behavior: Behavior,
+    // tree node the behavior came from, None for interrupts and AIs without a tree
+    pub node_id: Option<u32>,
+    // every node in the tree, root first
+    pub tree: Vec<Node>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TraceEntry {
+    pub time: u128,
+    pub node_id: Option<u32>,
+    pub behavior: Behavior,
+}
+
+/// Recent behavior transitions of a single enemy
+#[derive(Debug, Clone, Default)]
+pub struct TraceLog {
+    entries: VecDeque<TraceEntry>,
+}
+
+impl TraceLog {
+    pub fn record(&mut self, time: u128, node_id: Option<u32>, behavior: Behavior) {
+        if self.entries.len() == MAX_TRACE_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(TraceEntry {
+            time,
+            node_id,
+            behavior,
+        });
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &TraceEntry> {
+        self.entries.iter()
+    }
+}
+
+/// Every node reachable from the root, in breadth first order
+pub fn get_tree_nodes(behavior_tree: &dyn BehaviorTreeTrait) -> Vec<Node> {
+    let mut nodes = vec![behavior_tree.get_root().clone()];
+    let mut i = 0;
+    while i < nodes.len() {
+        if let Some((left, right)) = behavior_tree.get_node_children(nodes[i].get_id()) {
+            for child in [left, right] {
+                if let Some(node) = behavior_tree.get_node(child) {
+                    if !nodes.iter().any(|known| known.get_id() == child) {
+                        nodes.push(node.clone());
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+    nodes
+}
+
+/// Writes the trace of every enemy to `path`, one transition per line
+pub fn dump_traces(path: &str, traces: &[(EntityId, &TraceLog)]) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    for (id, trace) in traces {
+        for entry in trace.entries() {
+            let node = match entry.node_id {
+                Some(node_id) => node_id.to_string(),
+                None => "-".to_string(),
+            };
+            writeln!(
+                file,
+                "enemy={} t={}ms node={} behavior={:?}",
+                id, entry.time, node, entry.behavior
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use crate::behaviors::enemy_ai::debugger::{AiDebugInfo, TraceLog};
+use crate::behaviors::enemy_ai::model::{perform_behavior, EnemyAi};
+use crate::behaviors::enemy_ai::world_view::{AiCommand, WorldView};
+use crate::behaviors::model::Behavior;
+use crate::behaviors::steering::SteeringWeights;
+use anyhow::Result;
+
+// ms between decisions
+const DECISION_INTERVAL: u128 = 250;
+// px from the player a fighter opens fire from
+const ATTACK_RANGE: f32 = 350.0;
+// ms a fighter spends peeling off after an attack run
+const PEEL_OFF_DURATION: u128 = 1200;
+
+/// Flies attack runs at the player: closes in, fires, peels off and comes around again.
+/// Used by the fighters carriers launch.
+pub struct FighterEnemyAI {
+    current_behavior: Behavior,
+    last_decision: Option<u128>,
+    peel_off_until: u128,
+    trace: TraceLog,
+}
+
+impl EnemyAi for FighterEnemyAI {
+    fn new() -> Self {
+        FighterEnemyAI {
+            current_behavior: Behavior::MoveToPlayer,
+            last_decision: None,
+            peel_off_until: 0,
+            trace: TraceLog::default(),
+        }
+    }
+
+    fn perform_action(&mut self, world: &WorldView) -> Result<Vec<AiCommand>> {
+        let current_time = world.current_time;
+        if let Some(last) = self.last_decision {
+            if current_time - last < DECISION_INTERVAL {
+                return Ok(vec![]);
+            }
+        }
+        self.last_decision = Some(current_time);
+
+        let behavior = match current_time < self.peel_off_until {
+            true => Behavior::MoveToRandom,
+            false if world.get_distance_to_player() <= ATTACK_RANGE => {
+                self.peel_off_until = current_time + PEEL_OFF_DURATION;
+                Behavior::AttackPlayer
+            }
+            false => Behavior::MoveToPlayer,
+        };
+        // Keep heading for the same spot while peeling off
+        if behavior == Behavior::MoveToRandom && self.current_behavior == Behavior::MoveToRandom {
+            return Ok(vec![]);
+        }
+        if behavior != self.current_behavior {
+            self.trace.record(current_time, None, behavior.clone());
+        }
+        self.current_behavior = behavior;
+        Ok(perform_behavior(
+            &self.current_behavior,
+            world,
+            &mut rand::thread_rng(),
+        ))
+    }
+
+    fn get_debug_info(&self) -> Option<AiDebugInfo> {
+        Some(AiDebugInfo {
+            behavior: self.current_behavior.clone(),
+            node_id: None,
+            tree: vec![],
+        })
+    }
+
+    fn get_trace(&self) -> Option<&TraceLog> {
+        Some(&self.trace)
+    }
+
+    fn get_steering_weights(&self) -> SteeringWeights {
+        SteeringWeights {
+            goal: 1.4,
+            separation: 1.2,
+            cohesion: 0.3,
+            alignment: 0.4,
+            wander: 0.05,
+            max_acceleration: 2000.0,
+            turn_rate: 7.0,
+            ..SteeringWeights::default()
+        }
+    }
+}
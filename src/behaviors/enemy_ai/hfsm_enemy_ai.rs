@@ -0,0 +1,367 @@
+use crate::behaviors::enemy_ai::model::{perform_behavior, EnemyAi};
+use crate::behaviors::enemy_ai::world_view::{AiCommand, WeaponId, WorldView};
+use crate::behaviors::model::Behavior;
+use crate::behaviors::steering::{SteeringGoal, SteeringWeights};
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+#[cfg(feature = "game")]
+use std::io::Read;
+use thiserror::Error;
+
+const DEFAULT_HFSM_ENEMIES: &str = include_str!("../../../resources/hfsm_enemies.toml");
+const DEFAULT_HFSM_MACHINE: &str = "patrol_then_chase";
+
+#[derive(Debug, Error)]
+pub enum HfsmError {
+    #[cfg(feature = "game")]
+    #[error("failed to open state machines: {0}")]
+    Open(#[from] ggez::GameError),
+    #[error("failed to read state machines: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse state machines: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("state machine `{0}` refers to unknown state `{1}`")]
+    UnknownState(String, String),
+    #[error("state `{1}` in state machine `{0}` needs either children with an `initial` state or a `behavior`")]
+    InvalidState(String, String),
+    #[error("state `{1}` in state machine `{0}` is nested inside itself")]
+    ParentCycle(String, String),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HfsmFile {
+    pub machines: Vec<HfsmDefinition>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HfsmDefinition {
+    pub name: String,
+    pub initial: String,
+    pub states: Vec<HfsmStateDefinition>,
+    #[serde(default)]
+    pub transitions: Vec<HfsmTransition>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HfsmStateDefinition {
+    pub name: String,
+    pub parent: Option<String>,
+    // child state entered along with this one, only for states with children
+    pub initial: Option<String>,
+    // only for leaf states
+    pub behavior: Option<Behavior>,
+    // ms between repeats of the leaf behavior
+    #[serde(default = "default_interval")]
+    pub interval: f32,
+    #[serde(default)]
+    pub on_enter: Vec<HfsmHook>,
+    #[serde(default)]
+    pub on_exit: Vec<HfsmHook>,
+}
+
+fn default_interval() -> f32 {
+    800.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HfsmHook {
+    Fire,
+    Stop,
+    Log,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HfsmTransition {
+    pub from: String,
+    pub to: String,
+    pub guard: HfsmGuard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HfsmGuard {
+    Always,
+    // ms spent in the `from` state
+    Timer { ms: f32 },
+    PlayerWithin { distance: f32 },
+    PlayerBeyond { distance: f32 },
+    ProjectileWithin { distance: f32 },
+    HpBelow { ratio: f32 },
+}
+
+impl HfsmDefinition {
+    fn validate(&self) -> Result<(), HfsmError> {
+        let unknown = |name: &String| HfsmError::UnknownState(self.name.clone(), name.clone());
+        let names: Vec<&String> = self.states.iter().map(|state| &state.name).collect();
+        if !names.contains(&&self.initial) {
+            return Err(unknown(&self.initial));
+        }
+        for state in &self.states {
+            for name in state.parent.iter().chain(state.initial.iter()) {
+                if !names.contains(&name) {
+                    return Err(unknown(name));
+                }
+            }
+            // walking up the parents must reach a top level state
+            let mut parent = state.parent.as_ref();
+            for _ in 0..self.states.len() {
+                parent = parent.and_then(|name| {
+                    self.states
+                        .iter()
+                        .find(|state| &state.name == name)
+                        .and_then(|state| state.parent.as_ref())
+                });
+            }
+            if parent.is_some() {
+                return Err(HfsmError::ParentCycle(
+                    self.name.clone(),
+                    state.name.clone(),
+                ));
+            }
+            let has_children = self
+                .states
+                .iter()
+                .any(|child| child.parent.as_ref() == Some(&state.name));
+            let is_valid = match has_children {
+                true => {
+                    let is_initial_a_child = self.states.iter().any(|child| {
+                        Some(&child.name) == state.initial.as_ref()
+                            && child.parent.as_ref() == Some(&state.name)
+                    });
+                    is_initial_a_child && state.behavior.is_none()
+                }
+                false => state.initial.is_none() && state.behavior.is_some(),
+            };
+            if !is_valid {
+                return Err(HfsmError::InvalidState(
+                    self.name.clone(),
+                    state.name.clone(),
+                ));
+            }
+        }
+        for transition in &self.transitions {
+            for name in [&transition.from, &transition.to] {
+                if !names.contains(&name) {
+                    return Err(unknown(name));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn parse_hfsm_definitions(source: &str) -> Result<HashMap<String, HfsmDefinition>, HfsmError> {
+    let file: HfsmFile = toml::from_str(source)?;
+    let mut machines = HashMap::new();
+    for machine in file.machines {
+        machine.validate()?;
+        machines.insert(machine.name.clone(), machine);
+    }
+    Ok(machines)
+}
+
+#[cfg(feature = "game")]
+pub fn load_hfsm_definitions(
+    ctx: &ggez::Context,
+    path: &str,
+) -> Result<HashMap<String, HfsmDefinition>, HfsmError> {
+    let mut source = String::new();
+    ctx.fs.open(path)?.read_to_string(&mut source)?;
+    parse_hfsm_definitions(&source)
+}
+
+struct ActiveState {
+    state: usize,
+    entered_at: u128,
+}
+
+/// Runs a hierarchical state machine loaded from data, each leaf state maps to a `Behavior`
+pub struct HfsmEnemyAI {
+    definition: HfsmDefinition,
+    // from the outermost state down to the running leaf
+    active: Vec<ActiveState>,
+    last_performed: Option<u128>,
+    hp: f32,
+    max_hp: f32,
+}
+
+impl HfsmEnemyAI {
+    pub fn from_definition(definition: HfsmDefinition) -> HfsmEnemyAI {
+        HfsmEnemyAI {
+            definition,
+            active: vec![],
+            last_performed: None,
+            hp: 0.0,
+            max_hp: 0.0,
+        }
+    }
+
+    fn get_state_index(&self, name: &str) -> usize {
+        self.definition
+            .states
+            .iter()
+            .position(|state| state.name == name)
+            .expect("state machines are validated on load")
+    }
+
+    /// The state itself plus every state it is nested in, outermost first
+    fn get_ancestors(&self, state: usize) -> Vec<usize> {
+        let mut path = vec![state];
+        while let Some(parent) = &self.definition.states[path[0]].parent {
+            path.insert(0, self.get_state_index(parent));
+        }
+        path
+    }
+
+    fn change_state(&mut self, target: &str, current_time: u128, commands: &mut Vec<AiCommand>) {
+        let target = self.get_state_index(target);
+        let mut new_path = self.get_ancestors(target);
+        let target_depth = new_path.len() - 1;
+        while let Some(initial) = &self.definition.states[*new_path.last().unwrap()].initial {
+            new_path.push(self.get_state_index(initial));
+        }
+        // states shared with the current path stay active, except the target which re-enters
+        let shared = self
+            .active
+            .iter()
+            .zip(&new_path)
+            .take_while(|(active, state)| active.state == **state)
+            .count()
+            .min(target_depth);
+
+        while self.active.len() > shared {
+            let exited = self.active.pop().unwrap();
+            let hooks = self.definition.states[exited.state].on_exit.clone();
+            self.run_hooks(&hooks, commands);
+        }
+        for state in new_path.into_iter().skip(shared) {
+            self.active.push(ActiveState {
+                state,
+                entered_at: current_time,
+            });
+            let hooks = self.definition.states[state].on_enter.clone();
+            self.run_hooks(&hooks, commands);
+        }
+        // the new leaf runs its behavior straight away
+        self.last_performed = None;
+    }
+
+    fn run_hooks(&self, hooks: &[HfsmHook], commands: &mut Vec<AiCommand>) {
+        for hook in hooks {
+            match hook {
+                HfsmHook::Fire => commands.push(AiCommand::Fire(WeaponId::Blaster)),
+                HfsmHook::Stop => commands.push(AiCommand::Move(SteeringGoal::Hold)),
+                HfsmHook::Log => println!("[hfsm] {}", self.get_path()),
+            }
+        }
+    }
+
+    fn is_guard_passed(
+        &self,
+        guard: &HfsmGuard,
+        time_in_state: u128,
+        player_distance: f32,
+        projectile_distance: f32,
+    ) -> bool {
+        match guard {
+            HfsmGuard::Always => true,
+            HfsmGuard::Timer { ms } => time_in_state as f32 >= *ms,
+            HfsmGuard::PlayerWithin { distance } => player_distance <= *distance,
+            HfsmGuard::PlayerBeyond { distance } => player_distance > *distance,
+            HfsmGuard::ProjectileWithin { distance } => projectile_distance <= *distance,
+            HfsmGuard::HpBelow { ratio } => self.max_hp > 0.0 && self.hp / self.max_hp < *ratio,
+        }
+    }
+
+    /// Active states joined by `/`, e.g. `turret/engaging/firing`
+    pub fn get_path(&self) -> String {
+        let mut path = vec![self.definition.name.as_str()];
+        for active in &self.active {
+            path.push(self.definition.states[active.state].name.as_str());
+        }
+        path.join("/")
+    }
+}
+
+impl EnemyAi for HfsmEnemyAI {
+    fn new() -> Self {
+        let mut machines = parse_hfsm_definitions(DEFAULT_HFSM_ENEMIES)
+            .expect("Failed to parse the bundled state machines");
+        let definition = machines
+            .remove(DEFAULT_HFSM_MACHINE)
+            .expect("Bundled state machines are missing the default machine");
+        HfsmEnemyAI::from_definition(definition)
+    }
+
+    fn perform_action(&mut self, world: &WorldView) -> Result<Vec<AiCommand>> {
+        let current_time = world.current_time;
+        let enemy_position = world.actor.position;
+        self.hp = world.actor.hp;
+        self.max_hp = self.max_hp.max(world.actor.hp);
+        let mut commands = vec![];
+        if self.active.is_empty() {
+            let initial = self.definition.initial.clone();
+            self.change_state(&initial, current_time, &mut commands);
+        }
+
+        let player_distance = world.get_distance_to_player();
+        let projectile_distance = world
+            .get_hostile_projectile_positions()
+            .iter()
+            .map(|projectile| {
+                ((projectile.0 - enemy_position.0).powi(2)
+                    + (projectile.1 - enemy_position.1).powi(2))
+                .sqrt()
+            })
+            .fold(f32::MAX, f32::min);
+
+        // Outer states get the first say, at most one transition fires per frame
+        let next_state = self.active.iter().find_map(|active| {
+            let name = &self.definition.states[active.state].name;
+            self.definition
+                .transitions
+                .iter()
+                .filter(|transition| &transition.from == name)
+                .find(|transition| {
+                    self.is_guard_passed(
+                        &transition.guard,
+                        current_time - active.entered_at,
+                        player_distance,
+                        projectile_distance,
+                    )
+                })
+                .map(|transition| transition.to.clone())
+        });
+        if let Some(next_state) = next_state {
+            self.change_state(&next_state, current_time, &mut commands);
+        }
+
+        let leaf = &self.definition.states[self.active.last().unwrap().state];
+        let is_due = self
+            .last_performed
+            .is_none_or(|last| (current_time - last) as f32 >= leaf.interval);
+        if let (true, Some(behavior)) = (is_due, &leaf.behavior) {
+            commands.extend(perform_behavior(behavior, world, &mut rand::thread_rng()));
+            self.last_performed = Some(current_time);
+        }
+
+        Ok(commands)
+    }
+
+    fn get_state_path(&self) -> Option<String> {
+        Some(self.get_path())
+    }
+
+    fn get_steering_weights(&self) -> SteeringWeights {
+        SteeringWeights {
+            goal: 1.2,
+            separation: 1.2,
+            cohesion: 0.1,
+            alignment: 0.1,
+            wander: 0.1,
+            ..SteeringWeights::default()
+        }
+    }
+}
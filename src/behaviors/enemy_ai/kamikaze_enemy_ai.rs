@@ -0,0 +1,87 @@
+use crate::behaviors::enemy_ai::debugger::{AiDebugInfo, TraceLog};
+use crate::behaviors::enemy_ai::model::{perform_behavior, EnemyAi};
+use crate::behaviors::enemy_ai::world_view::{AiCommand, WorldView};
+use crate::behaviors::model::Behavior;
+use crate::behaviors::steering::SteeringWeights;
+use anyhow::Result;
+
+// ms between decisions
+const DECISION_INTERVAL: u128 = 200;
+// px from the player a ram is started from
+const RAM_RANGE: f32 = 550.0;
+// ms between rams, long enough for the wind-up, charge and recovery to play out
+const RAM_INTERVAL: u128 = 3500;
+
+/// Closes in on the player and rams it whenever it gets in range, for kamikaze enemies
+pub struct KamikazeEnemyAI {
+    current_behavior: Behavior,
+    last_decision: Option<u128>,
+    last_ram: Option<u128>,
+    trace: TraceLog,
+}
+
+impl EnemyAi for KamikazeEnemyAI {
+    fn new() -> Self {
+        KamikazeEnemyAI {
+            current_behavior: Behavior::MoveToPlayer,
+            last_decision: None,
+            last_ram: None,
+            trace: TraceLog::default(),
+        }
+    }
+
+    fn perform_action(&mut self, world: &WorldView) -> Result<Vec<AiCommand>> {
+        let current_time = world.current_time;
+        if let Some(last) = self.last_decision {
+            if current_time - last < DECISION_INTERVAL {
+                return Ok(vec![]);
+            }
+        }
+        self.last_decision = Some(current_time);
+
+        let is_ram_ready = self
+            .last_ram
+            .is_none_or(|last| current_time - last >= RAM_INTERVAL);
+        let behavior = match is_ram_ready && world.get_distance_to_player() <= RAM_RANGE {
+            true => {
+                self.last_ram = Some(current_time);
+                Behavior::Ram
+            }
+            false => Behavior::MoveToPlayer,
+        };
+        if behavior != self.current_behavior {
+            self.trace.record(current_time, None, behavior.clone());
+        }
+        self.current_behavior = behavior;
+        Ok(perform_behavior(
+            &self.current_behavior,
+            world,
+            &mut rand::thread_rng(),
+        ))
+    }
+
+    fn get_debug_info(&self) -> Option<AiDebugInfo> {
+        Some(AiDebugInfo {
+            behavior: self.current_behavior.clone(),
+            node_id: None,
+            tree: vec![],
+        })
+    }
+
+    fn get_trace(&self) -> Option<&TraceLog> {
+        Some(&self.trace)
+    }
+
+    fn get_steering_weights(&self) -> SteeringWeights {
+        SteeringWeights {
+            goal: 1.6,
+            separation: 0.8,
+            cohesion: 0.0,
+            alignment: 0.1,
+            wander: 0.0,
+            max_acceleration: 2200.0,
+            turn_rate: 8.0,
+            ..SteeringWeights::default()
+        }
+    }
+}
@@ -1,4 +1,13 @@
+pub mod action_timing;
 pub mod aggressive_enemy_ai;
+pub mod data_tree_enemy_ai;
+pub mod debugger;
 pub mod elusive_enemy_ai;
+pub mod fighter_enemy_ai;
+pub mod hfsm_enemy_ai;
+pub mod kamikaze_enemy_ai;
 pub mod model;
 pub mod normal_enemy_ai;
+pub mod perception;
+pub mod utility_enemy_ai;
+pub mod world_view;
@@ -1,4 +1,10 @@
-use crate::behaviors::model::{Behavior, BehaviorTreeTrait, NodeTrait};
+use crate::behaviors::enemy_ai::aggressive_enemy_ai::AggressiveEnemyAI;
+use crate::behaviors::enemy_ai::debugger::{AiDebugInfo, TraceLog};
+use crate::behaviors::enemy_ai::elusive_enemy_ai::ElusiveEnemyAI;
+use crate::behaviors::enemy_ai::normal_enemy_ai::NormalEnemyAI;
+use crate::behaviors::enemy_ai::world_view::{AiCommand, HazardView, WeaponId, WorldView};
+use crate::behaviors::model::{Behavior, BehaviorTreeTrait, BehaviorTreeType, NodeTrait};
+use crate::behaviors::steering::{SteeringGoal, SteeringWeights};
 use rand::Rng;
 use std::cmp::Ordering;
 
@@ -6,14 +12,29 @@ pub trait EnemyAi {
     fn new() -> Self
     where
         Self: Sized;
-    fn perform_action(
-        &mut self,
-        current_time: u128,
-        player_position: (f32, f32),
-        enemy_position: (f32, f32),
-        speed: f32,
-        projectile_positions: Vec<(f32, f32)>,
-    ) -> Result<ActionResult, anyhow::Error>;
+    fn perform_action(&mut self, world: &WorldView) -> Result<Vec<AiCommand>, anyhow::Error>;
+    /// Human readable description of the running state, for AIs that have one
+    fn get_state_path(&self) -> Option<String> {
+        None
+    }
+    /// What the AI is running right now, for the debug overlay
+    fn get_debug_info(&self) -> Option<AiDebugInfo> {
+        None
+    }
+    fn get_trace(&self) -> Option<&TraceLog> {
+        None
+    }
+    fn get_steering_weights(&self) -> SteeringWeights {
+        SteeringWeights::default()
+    }
+}
+
+pub fn create_enemy_ai(behavior_tree_type: BehaviorTreeType) -> Box<dyn EnemyAi> {
+    match behavior_tree_type {
+        BehaviorTreeType::NormalEnemy => Box::new(NormalEnemyAI::new()),
+        BehaviorTreeType::AggressiveEnemy => Box::new(AggressiveEnemyAI::new()),
+        BehaviorTreeType::ElusiveEnemy => Box::new(ElusiveEnemyAI::new()),
+    }
 }
 
 #[derive(Eq, PartialEq, Clone, Debug)]
@@ -35,10 +56,55 @@ impl PartialOrd for BehaviorAction {
     }
 }
 
-pub struct ActionResult {
-    pub enemy_position: (f32, f32),
-    pub enemy_target: (f32, f32),
-    pub is_attacking: bool,
+/// Commands for a single behavior, for AIs that pick behaviors without a tree
+pub fn perform_behavior(
+    behavior: &Behavior,
+    world: &WorldView,
+    rng: &mut impl Rng,
+) -> Vec<AiCommand> {
+    let enemy_position = world.actor.position;
+    let player_position = world.player.position;
+    match behavior {
+        Behavior::Idle => vec![AiCommand::Move(SteeringGoal::Hold)],
+        Behavior::MoveToRandom => {
+            let x = rng.gen_range(
+                (enemy_position.0 - 1000.0).min(0.0)
+                    ..(enemy_position.0 + 1000.0).max(world.arena.0),
+            );
+            let y = rng.gen_range(
+                (enemy_position.1 - 1000.0).min(0.0)
+                    ..(enemy_position.1 + 1000.0).max(world.arena.1),
+            );
+            vec![AiCommand::Move(SteeringGoal::Arrive((x, y)))]
+        }
+        Behavior::MoveToPlayer => vec![AiCommand::Move(SteeringGoal::PursuePlayer)],
+        Behavior::AttackRandom => {
+            let x = rng.gen_range(
+                (player_position.0 - 500.0).min(0.0)
+                    ..(player_position.0 + 500.0).max(world.arena.0),
+            );
+            let y = rng.gen_range(
+                (player_position.1 - 500.0).min(0.0)
+                    ..(player_position.1 + 500.0).max(world.arena.1),
+            );
+            vec![AiCommand::Aim((x, y)), AiCommand::Fire(WeaponId::Blaster)]
+        }
+        Behavior::AttackPlayer => vec![
+            AiCommand::Aim(player_position),
+            AiCommand::Fire(WeaponId::Blaster),
+        ],
+        Behavior::RunAway => vec![AiCommand::Move(SteeringGoal::EvadePlayer)],
+        Behavior::Dodge => vec![AiCommand::Move(SteeringGoal::Seek(
+            calculate_dodge_position(
+                enemy_position,
+                world.get_hostile_projectile_positions(),
+                world.hazards,
+                world.actor.max_speed,
+                rng,
+            ),
+        ))],
+        Behavior::Ram => vec![AiCommand::Ram(player_position)],
+    }
 }
 
 pub fn get_next_child_actions(
@@ -87,7 +153,9 @@ pub fn get_next_child_actions(
 pub fn calculate_dodge_position(
     enemy_position: (f32, f32),
     projectile_positions: Vec<(f32, f32)>,
+    hazards: &[HazardView],
     speed: f32,
+    rng: &mut impl Rng,
 ) -> (f32, f32) {
     // check if there are any projectiles nearby
     let mut nearby_projectiles = Vec::new();
@@ -96,31 +164,39 @@ pub fn calculate_dodge_position(
         let dy = projectile_position.1 - enemy_position.1;
         let distance = (dx.powf(2.0) + dy.powf(2.0)).sqrt();
         if distance < 50.0 {
-            nearby_projectiles.push(projectile_position);
+            nearby_projectiles.push((projectile_position, distance));
+        }
+    }
+    // hazards count as threats once their edge is as close as a projectile would have to be
+    for hazard in hazards {
+        let dx = hazard.position.0 - enemy_position.0;
+        let dy = hazard.position.1 - enemy_position.1;
+        let distance = ((dx.powf(2.0) + dy.powf(2.0)).sqrt() - hazard.radius).max(0.0);
+        if distance < 50.0 {
+            nearby_projectiles.push((hazard.position, distance));
         }
     }
 
     // if there are nearby projectiles, dodge them
     if !nearby_projectiles.is_empty() {
         // get the closest projectile
-        nearby_projectiles.sort_by(|a, b| {
-            let dx_a = a.0 - enemy_position.0;
-            let dy_a = a.1 - enemy_position.1;
-            let distance_a = (dx_a.powf(2.0) + dy_a.powf(2.0)).sqrt();
-            let dx_b = b.0 - enemy_position.0;
-            let dy_b = b.1 - enemy_position.1;
-            let distance_b = (dx_b.powf(2.0) + dy_b.powf(2.0)).sqrt();
-            distance_a.partial_cmp(&distance_b).unwrap()
-        });
+        nearby_projectiles.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
 
         let mut dodge_direction = (0.0, 0.0);
 
         // Calculate the direction to dodge the nearest projectile
-        let closest_projectile = nearby_projectiles[0];
+        let (closest_projectile, _) = nearby_projectiles[0];
         let dx = closest_projectile.0 - enemy_position.0;
         let dy = closest_projectile.1 - enemy_position.1;
         let magnitude = (dx.powf(2.0) + dy.powf(2.0)).sqrt();
-        dodge_direction = (dx / magnitude, dy / magnitude);
+        dodge_direction = match magnitude > 0.0 {
+            true => (dx / magnitude, dy / magnitude),
+            // Standing right on top of a hazard, any way out will do
+            false => {
+                let angle = rng.gen_range(0.0..2.0 * std::f32::consts::PI);
+                (angle.cos(), angle.sin())
+            }
+        };
         // Normalize the dodge direction
         let magnitude = (dodge_direction.0.powf(2.0) + dodge_direction.1.powf(2.0)).sqrt();
         let direction = (dodge_direction.0 / magnitude, dodge_direction.1 / magnitude);
@@ -132,7 +208,6 @@ pub fn calculate_dodge_position(
     } else {
         // If there are no nearby projectiles, move randomly
         // Generate a random angle and distance
-        let mut rng = rand::thread_rng();
         let angle = rng.gen_range(0.0..2.0 * std::f32::consts::PI);
         let distance = rng.gen_range(0.0..500.0); // Set this to the desired dodge distance
 
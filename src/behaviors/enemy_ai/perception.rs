@@ -0,0 +1,238 @@
+use crate::behaviors::enemy_ai::world_view::{ActorView, AiCommand, Faction, ProjectileView};
+use crate::behaviors::steering::SteeringGoal;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+#[cfg(feature = "game")]
+use std::io::Read;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PerceptionError {
+    #[cfg(feature = "game")]
+    #[error("failed to open perception profiles: {0}")]
+    Open(#[from] ggez::GameError),
+    #[error("failed to read perception profiles: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse perception profiles: {0}")]
+    Parse(#[from] toml::de::Error),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PerceptionProfiles {
+    pub profiles: HashMap<String, PerceptionProfile>,
+}
+
+impl PerceptionProfiles {
+    /// The named profile, or one that sees everything straight away
+    pub fn get_profile(&self, name: &str) -> PerceptionProfile {
+        self.profiles.get(name).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct PerceptionProfile {
+    pub sight_radius: f32,
+    // full width of the vision cone in degrees, centered on the enemy's heading
+    pub field_of_view: f32,
+    // anything this close is noticed whichever way the enemy faces
+    pub proximity_radius: f32,
+    // all durations are in ms
+    pub reaction_delay: f32,
+    // how long the last seen player position is chased after losing sight
+    pub memory: f32,
+    // player shots passing this close give the player away
+    pub hearing_radius: f32,
+    // a player moving faster than this, like during a dash, is a blur the enemy can't follow
+    pub max_tracking_speed: Option<f32>,
+}
+
+impl Default for PerceptionProfile {
+    fn default() -> Self {
+        PerceptionProfile {
+            sight_radius: f32::MAX,
+            field_of_view: 360.0,
+            proximity_radius: 0.0,
+            reaction_delay: 0.0,
+            memory: 0.0,
+            hearing_radius: 0.0,
+            max_tracking_speed: None,
+        }
+    }
+}
+
+#[cfg(feature = "game")]
+pub fn load_perception_profiles(
+    ctx: &ggez::Context,
+    path: &str,
+) -> Result<PerceptionProfiles, PerceptionError> {
+    let mut source = String::new();
+    ctx.fs.open(path)?.read_to_string(&mut source)?;
+    Ok(toml::from_str(&source)?)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Awareness {
+    Seen,
+    // lost sight of the player, but still going after the last place it was seen
+    Remembered,
+    Unaware,
+}
+
+/// What one enemy knows about the player. Sits between the world and the enemy's AI: the AI
+/// gets the player as last perceived, a reaction delay late, and only the projectiles it can
+/// see, and its commands are toned down when it has lost track of the player.
+pub struct Perception {
+    pub profile: PerceptionProfile,
+    pub awareness: Awareness,
+    // unit vector the enemy faces, the direction it last moved in
+    heading: (f32, f32),
+    // what the senses picked up each frame, acted on once the reaction delay has passed
+    sightings: VecDeque<(u128, Option<ActorView>)>,
+    last_seen: Option<(ActorView, u128)>,
+}
+
+impl Perception {
+    pub fn new(profile: PerceptionProfile) -> Perception {
+        Perception {
+            profile,
+            awareness: Awareness::Unaware,
+            // enemies come in from the top of the screen
+            heading: (0.0, 1.0),
+            sightings: VecDeque::new(),
+            last_seen: None,
+        }
+    }
+
+    fn can_see(&self, actor: &ActorView, target: (f32, f32)) -> bool {
+        let offset = (target.0 - actor.position.0, target.1 - actor.position.1);
+        let distance = (offset.0.powi(2) + offset.1.powi(2)).sqrt();
+        if distance <= self.profile.proximity_radius || distance <= f32::EPSILON {
+            return true;
+        }
+        if distance > self.profile.sight_radius {
+            return false;
+        }
+        let cos_angle = (offset.0 * self.heading.0 + offset.1 * self.heading.1) / distance;
+        cos_angle
+            >= (self.profile.field_of_view / 2.0)
+                .min(180.0)
+                .to_radians()
+                .cos()
+    }
+
+    /// Updates what the enemy knows and returns the player and projectiles as it perceives them
+    pub fn perceive(
+        &mut self,
+        actor: &ActorView,
+        player: &ActorView,
+        projectiles: &[ProjectileView],
+        current_time: u128,
+    ) -> (ActorView, Vec<ProjectileView>) {
+        let speed = (actor.velocity.0.powi(2) + actor.velocity.1.powi(2)).sqrt();
+        if speed > 1.0 {
+            self.heading = (actor.velocity.0 / speed, actor.velocity.1 / speed);
+        }
+
+        let player_speed = (player.velocity.0.powi(2) + player.velocity.1.powi(2)).sqrt();
+        let is_trackable = self
+            .profile
+            .max_tracking_speed
+            .is_none_or(|max_speed| player_speed <= max_speed);
+        let is_seen = is_trackable && self.can_see(actor, player.position);
+        let is_heard = projectiles.iter().any(|projectile| {
+            projectile.faction == Faction::Player
+                && ((projectile.position.0 - actor.position.0).powi(2)
+                    + (projectile.position.1 - actor.position.1).powi(2))
+                .sqrt()
+                    <= self.profile.hearing_radius
+        });
+        let sighting = match is_seen || is_heard {
+            true => Some(*player),
+            false => None,
+        };
+        self.sightings.push_back((current_time, sighting));
+
+        // Only act on what was noticed at least a reaction delay ago
+        let reaction_delay = self.profile.reaction_delay as u128;
+        while let Some((time, sighting)) = self.sightings.front().copied() {
+            if time + reaction_delay > current_time {
+                break;
+            }
+            self.sightings.pop_front();
+            match sighting {
+                Some(view) => {
+                    self.last_seen = Some((view, time));
+                    self.awareness = Awareness::Seen;
+                }
+                None if self.awareness == Awareness::Seen => {
+                    self.awareness = Awareness::Remembered;
+                }
+                None => (),
+            }
+        }
+        if self.awareness == Awareness::Remembered {
+            let is_forgotten = self.last_seen.is_none_or(|(_, time)| {
+                current_time.saturating_sub(time) as f32 > self.profile.memory
+            });
+            if is_forgotten {
+                self.awareness = Awareness::Unaware;
+            }
+        }
+
+        let perceived_player = match (self.awareness, self.last_seen) {
+            (Awareness::Seen, Some((view, _))) => view,
+            (Awareness::Remembered, Some((view, _))) => ActorView {
+                velocity: (0.0, 0.0),
+                ..view
+            },
+            // no idea where the player is
+            _ => ActorView {
+                position: actor.position,
+                velocity: (0.0, 0.0),
+                ..*player
+            },
+        };
+        let perceived_projectiles = projectiles
+            .iter()
+            .filter(|projectile| {
+                projectile.faction == Faction::Enemy || self.can_see(actor, projectile.position)
+            })
+            .copied()
+            .collect();
+        (perceived_player, perceived_projectiles)
+    }
+
+    /// Keeps the AI from chasing or shooting at a player it has lost track of
+    pub fn filter_commands(&self, commands: Vec<AiCommand>) -> Vec<AiCommand> {
+        let last_seen = self.last_seen.map(|(view, _)| view.position);
+        match (self.awareness, last_seen) {
+            (Awareness::Seen, _) => commands,
+            (Awareness::Remembered, Some(last_seen)) => commands
+                .into_iter()
+                .map(|command| match command {
+                    AiCommand::Move(SteeringGoal::PursuePlayer) => {
+                        AiCommand::Move(SteeringGoal::Arrive(last_seen))
+                    }
+                    AiCommand::Move(SteeringGoal::EvadePlayer) => {
+                        AiCommand::Move(SteeringGoal::Flee(last_seen))
+                    }
+                    command => command,
+                })
+                .collect(),
+            _ => commands
+                .into_iter()
+                .filter_map(|command| match command {
+                    AiCommand::Move(SteeringGoal::PursuePlayer)
+                    | AiCommand::Move(SteeringGoal::EvadePlayer) => {
+                        Some(AiCommand::Move(SteeringGoal::Wander))
+                    }
+                    AiCommand::Move(goal) => Some(AiCommand::Move(goal)),
+                    AiCommand::Aim(_)
+                    | AiCommand::Fire(_)
+                    | AiCommand::Sound(_)
+                    | AiCommand::Ram(_) => None,
+                })
+                .collect(),
+        }
+    }
+}
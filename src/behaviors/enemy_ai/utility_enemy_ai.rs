@@ -0,0 +1,310 @@
+use crate::behaviors::enemy_ai::debugger::{AiDebugInfo, TraceLog};
+use crate::behaviors::enemy_ai::model::{perform_behavior, EnemyAi};
+use crate::behaviors::enemy_ai::world_view::{AiCommand, WorldView};
+use crate::behaviors::model::Behavior;
+use crate::behaviors::steering::SteeringWeights;
+use anyhow::Result;
+use serde::Deserialize;
+#[cfg(feature = "game")]
+use std::io::Read;
+use thiserror::Error;
+
+const DEFAULT_UTILITY_CURVES: &str = include_str!("../../../resources/utility_curves.toml");
+// projectiles closer than this count as incoming
+const INCOMING_PROJECTILE_RADIUS: f32 = 150.0;
+
+#[derive(Debug, Error)]
+pub enum UtilityCurveError {
+    #[cfg(feature = "game")]
+    #[error("failed to open utility curves: {0}")]
+    Open(#[from] ggez::GameError),
+    #[error("failed to read utility curves: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse utility curves: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("utility curves define no actions")]
+    Empty,
+    #[error("{0:?} has a weight or curve that doesn't give a score in 0..1")]
+    InvalidCurve(Behavior),
+    #[error("{0:?} has a consideration whose min isn't below its max")]
+    InvalidRange(Behavior),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UtilityConfig {
+    // bonus added to the running action's score
+    pub hysteresis: f32,
+    // ms between re-evaluating the actions
+    pub decision_interval: f32,
+    pub actions: Vec<UtilityAction>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UtilityAction {
+    pub behavior: Behavior,
+    pub weight: f32,
+    pub considerations: Vec<Consideration>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Consideration {
+    pub input: UtilityInput,
+    pub min: f32,
+    pub max: f32,
+    pub curve: ResponseCurve,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UtilityInput {
+    DistanceToPlayer,
+    HpRatio,
+    IncomingProjectiles,
+    TimeSinceAttack,
+}
+
+/// Shapes a normalized input in 0..1 into a score in 0..1
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseCurve {
+    Linear {
+        slope: f32,
+        intercept: f32,
+    },
+    Power {
+        exponent: f32,
+        slope: f32,
+        intercept: f32,
+    },
+    Logistic {
+        steepness: f32,
+        midpoint: f32,
+    },
+    Step {
+        threshold: f32,
+    },
+}
+
+impl ResponseCurve {
+    pub fn evaluate(&self, x: f32) -> f32 {
+        let score = match self {
+            ResponseCurve::Linear { slope, intercept } => slope * x + intercept,
+            ResponseCurve::Power {
+                exponent,
+                slope,
+                intercept,
+            } => slope * x.powf(*exponent) + intercept,
+            ResponseCurve::Logistic {
+                steepness,
+                midpoint,
+            } => 1.0 / (1.0 + (-steepness * (x - midpoint)).exp()),
+            ResponseCurve::Step { threshold } => match x >= *threshold {
+                true => 1.0,
+                false => 0.0,
+            },
+        };
+        score.clamp(0.0, 1.0)
+    }
+
+    /// Whether the curve scores every input in 0..1 without going NaN
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            ResponseCurve::Linear { slope, intercept } => {
+                slope.is_finite() && intercept.is_finite()
+            }
+            // negative exponents blow up to infinity at 0
+            ResponseCurve::Power {
+                exponent,
+                slope,
+                intercept,
+            } => {
+                exponent.is_finite()
+                    && exponent >= 0.0
+                    && slope.is_finite()
+                    && intercept.is_finite()
+            }
+            ResponseCurve::Logistic {
+                steepness,
+                midpoint,
+            } => steepness.is_finite() && midpoint.is_finite(),
+            ResponseCurve::Step { threshold } => threshold.is_finite(),
+        }
+    }
+}
+
+pub fn parse_utility_config(source: &str) -> Result<UtilityConfig, UtilityCurveError> {
+    let config: UtilityConfig = toml::from_str(source)?;
+    if config.actions.is_empty() {
+        return Err(UtilityCurveError::Empty);
+    }
+    for action in &config.actions {
+        let behavior = action.behavior.clone();
+        if !action.weight.is_finite() || action.considerations.iter().any(|c| !c.curve.is_valid()) {
+            return Err(UtilityCurveError::InvalidCurve(behavior));
+        }
+        if action
+            .considerations
+            .iter()
+            .any(|c| !c.min.is_finite() || !c.max.is_finite() || c.min >= c.max)
+        {
+            return Err(UtilityCurveError::InvalidRange(behavior));
+        }
+    }
+    Ok(config)
+}
+
+#[cfg(feature = "game")]
+pub fn load_utility_config(
+    ctx: &ggez::Context,
+    path: &str,
+) -> Result<UtilityConfig, UtilityCurveError> {
+    let mut source = String::new();
+    ctx.fs.open(path)?.read_to_string(&mut source)?;
+    parse_utility_config(&source)
+}
+
+/// What the utility AI knows about its surroundings when scoring actions
+struct UtilityInputs {
+    distance_to_player: f32,
+    hp_ratio: f32,
+    incoming_projectiles: f32,
+    time_since_attack: f32,
+}
+
+impl UtilityInputs {
+    fn get(&self, input: UtilityInput) -> f32 {
+        match input {
+            UtilityInput::DistanceToPlayer => self.distance_to_player,
+            UtilityInput::HpRatio => self.hp_ratio,
+            UtilityInput::IncomingProjectiles => self.incoming_projectiles,
+            UtilityInput::TimeSinceAttack => self.time_since_attack,
+        }
+    }
+}
+
+/// Scores every behavior with response curves and runs the best one, instead of walking a
+/// behavior tree
+pub struct UtilityEnemyAI {
+    config: UtilityConfig,
+    current_behavior: Behavior,
+    last_decision: u128,
+    last_attack: u128,
+    // highest hp seen, enemies spawn at full health
+    max_hp: f32,
+    trace: TraceLog,
+}
+
+impl UtilityEnemyAI {
+    pub fn from_config(config: UtilityConfig) -> UtilityEnemyAI {
+        UtilityEnemyAI {
+            config,
+            current_behavior: Behavior::Idle,
+            last_decision: 0,
+            last_attack: 0,
+            max_hp: 0.0,
+            trace: TraceLog::default(),
+        }
+    }
+
+    fn score_action(&self, action: &UtilityAction, inputs: &UtilityInputs) -> f32 {
+        let mut score = action.considerations.iter().fold(action.weight, |acc, c| {
+            let range = (c.max - c.min).max(f32::EPSILON);
+            let x = ((inputs.get(c.input) - c.min) / range).clamp(0.0, 1.0);
+            acc * c.curve.evaluate(x)
+        });
+        if action.behavior == self.current_behavior {
+            score += self.config.hysteresis;
+        }
+        score
+    }
+
+    fn choose_behavior(&self, inputs: &UtilityInputs) -> Behavior {
+        self.config
+            .actions
+            .iter()
+            .map(|action| (action, self.score_action(action, inputs)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(action, _)| action.behavior.clone())
+            .unwrap_or(Behavior::Idle)
+    }
+}
+
+impl EnemyAi for UtilityEnemyAI {
+    fn new() -> Self {
+        let config = parse_utility_config(DEFAULT_UTILITY_CURVES)
+            .expect("Failed to parse the bundled utility curves");
+        UtilityEnemyAI::from_config(config)
+    }
+
+    fn perform_action(&mut self, world: &WorldView) -> Result<Vec<AiCommand>> {
+        let current_time = world.current_time;
+        if ((current_time - self.last_decision) as f32) < self.config.decision_interval {
+            return Ok(vec![]);
+        }
+        self.last_decision = current_time;
+        self.max_hp = self.max_hp.max(world.actor.hp);
+
+        let enemy_position = world.actor.position;
+        let incoming_projectiles = world
+            .get_hostile_projectile_positions()
+            .iter()
+            .filter(|projectile| {
+                ((projectile.0 - enemy_position.0).powi(2)
+                    + (projectile.1 - enemy_position.1).powi(2))
+                .sqrt()
+                    < INCOMING_PROJECTILE_RADIUS
+            })
+            .count() as f32;
+        let inputs = UtilityInputs {
+            distance_to_player: world.get_distance_to_player(),
+            hp_ratio: match self.max_hp > 0.0 {
+                true => world.actor.hp / self.max_hp,
+                false => 1.0,
+            },
+            incoming_projectiles,
+            time_since_attack: (current_time - self.last_attack) as f32,
+        };
+        let behavior = self.choose_behavior(&inputs);
+        if behavior != self.current_behavior {
+            self.trace.record(current_time, None, behavior.clone());
+        }
+        self.current_behavior = behavior;
+
+        if matches!(
+            self.current_behavior,
+            Behavior::AttackPlayer | Behavior::AttackRandom
+        ) {
+            self.last_attack = current_time;
+        }
+        Ok(perform_behavior(
+            &self.current_behavior,
+            world,
+            &mut rand::thread_rng(),
+        ))
+    }
+
+    fn get_debug_info(&self) -> Option<AiDebugInfo> {
+        Some(AiDebugInfo {
+            behavior: self.current_behavior.clone(),
+            node_id: None,
+            tree: vec![],
+        })
+    }
+
+    fn get_trace(&self) -> Option<&TraceLog> {
+        Some(&self.trace)
+    }
+
+    fn get_steering_weights(&self) -> SteeringWeights {
+        SteeringWeights {
+            goal: 1.2,
+            separation: 1.5,
+            cohesion: 0.2,
+            alignment: 0.2,
+            wander: 0.1,
+            max_acceleration: 1500.0,
+            turn_rate: 6.0,
+            ..SteeringWeights::default()
+        }
+    }
+}
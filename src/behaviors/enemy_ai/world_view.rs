@@ -0,0 +1,102 @@
+use crate::behaviors::steering::SteeringGoal;
+use crate::entity::EntityId;
+
+// Other enemies within this distance show up in `WorldView::allies`
+pub const NEARBY_ALLY_RADIUS: f32 = 400.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Faction {
+    Player,
+    Enemy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActorView {
+    pub id: EntityId,
+    pub position: (f32, f32),
+    pub velocity: (f32, f32),
+    pub max_speed: f32,
+    pub hp: f32,
+    pub attack_cooldown: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProjectileView {
+    pub position: (f32, f32),
+    pub velocity: (f32, f32),
+    pub faction: Faction,
+}
+
+/// Ground an enemy should stay out of, like a mine or a cloud
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HazardView {
+    pub position: (f32, f32),
+    pub radius: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WaveInfo {
+    pub wave: u32,
+    pub enemies_remaining: usize,
+    pub is_boss_wave: bool,
+}
+
+/// Read-only snapshot of the world an enemy's AI decides from each frame
+pub struct WorldView<'a> {
+    pub current_time: u128,
+    // the enemy the AI is controlling
+    pub actor: ActorView,
+    pub player: ActorView,
+    pub projectiles: &'a [ProjectileView],
+    pub hazards: &'a [HazardView],
+    // other enemies close to `actor`
+    pub allies: &'a [ActorView],
+    // width and height of the screen
+    pub arena: (f32, f32),
+    pub wave: WaveInfo,
+}
+
+impl WorldView<'_> {
+    pub fn get_distance_to_player(&self) -> f32 {
+        ((self.player.position.0 - self.actor.position.0).powi(2)
+            + (self.player.position.1 - self.actor.position.1).powi(2))
+        .sqrt()
+    }
+
+    /// Positions of the projectiles fired by the player, the only ones that can hurt enemies
+    pub fn get_hostile_projectile_positions(&self) -> Vec<(f32, f32)> {
+        self.projectiles
+            .iter()
+            .filter(|projectile| projectile.faction == Faction::Player)
+            .map(|projectile| projectile.position)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WeaponId {
+    /// Single shot
+    Blaster,
+    /// Three shot fan
+    Spread,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundCue {
+    Laser,
+    Charge,
+    // a shot glancing off a shield
+    Deflect,
+}
+
+/// What an AI asks its enemy to do this frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AiCommand {
+    Move(SteeringGoal),
+    Aim((f32, f32)),
+    // fires at the last aim point, or the player when nothing was aimed at
+    Fire(WeaponId),
+    Sound(SoundCue),
+    // wind up, then charge through the target along a locked vector
+    Ram((f32, f32)),
+}
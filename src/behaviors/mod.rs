@@ -3,3 +3,6 @@ mod elusive_enemy_behavior_tree;
 pub mod enemy_ai;
 pub(crate) mod model;
 mod normal_enemy_behavior_tree;
+pub mod squad;
+pub mod steering;
+pub mod tree_definition;
@@ -1,10 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
 pub enum BehaviorTreeType {
     NormalEnemy,
     AggressiveEnemy,
     ElusiveEnemy,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
 pub enum Behavior {
     Idle,
     MoveToPlayer,
@@ -13,6 +16,7 @@ pub enum Behavior {
     AttackRandom,
     RunAway,
     Dodge,
+    Ram,
 }
 
 pub struct EnemyBehaviors {
@@ -107,6 +111,7 @@ pub trait BehaviorTreeTrait {
     fn get_node_parent(&self, node_id: u32) -> Option<u32>;
     fn get_node_children(&self, node_id: u32) -> Option<(u32, u32)>;
     fn get_current_node(&self) -> u32;
+    fn set_current_node(&mut self, node_id: u32);
 }
 pub trait CustomBehaviorTreeTrait {
     fn from(behavior_tree: BehaviorTree) -> Self;
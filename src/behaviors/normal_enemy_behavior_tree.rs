@@ -1,6 +1,6 @@
 use crate::behaviors::model::{Behavior, BehaviorTreeTrait, EnemyBehaviors, Node, NodeTrait};
 
-fn get_normal_behaviors() -> EnemyBehaviors {
+pub(crate) fn get_normal_behaviors() -> EnemyBehaviors {
     EnemyBehaviors {
         root: Node::new(0, Behavior::Idle, None, Some((1, 2))),
         nodes: vec![
@@ -88,4 +88,8 @@ impl BehaviorTreeTrait for NormalEnemyBehaviorTree {
     fn get_current_node(&self) -> u32 {
         self.current_node
     }
+
+    fn set_current_node(&mut self, node_id: u32) {
+        self.current_node = node_id;
+    }
 }
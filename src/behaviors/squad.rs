@@ -0,0 +1,197 @@
+use crate::entity::EntityId;
+use std::collections::HashMap;
+
+const MAX_SQUAD_SIZE: usize = 4;
+// enemies further than this from a squad's centre start a squad of their own
+const SQUAD_JOIN_RADIUS: f32 = 600.0;
+// ms an enemy keeps its attack token before it is handed to someone else
+const ATTACK_TOKEN_DURATION: f32 = 3000.0;
+pub const FLANK_DISTANCE: f32 = 350.0;
+// degrees between squad mates around their flank
+const FLANK_SPACING: f32 = 25.0;
+// radians per second the whole formation circles the player
+const FLANK_ROTATION_SPEED: f32 = 0.2;
+
+pub struct Squad {
+    pub members: Vec<EntityId>,
+    // radians around the player the squad is centred on
+    pub flank_angle: f32,
+}
+
+pub struct AttackToken {
+    pub holder: EntityId,
+    pub remaining: f32,
+}
+
+/// Groups enemies into squads and hands out a limited number of attack tokens,
+/// so only a few enemies press the attack while the rest hold flanking positions
+pub struct SquadCoordinator {
+    pub squads: Vec<Squad>,
+    pub tokens: Vec<AttackToken>,
+    pub max_attack_tokens: usize,
+    // px from the player enemies without a token wait at
+    pub flank_distance: f32,
+    // ms each enemy has gone without a token, the longest waiting attack next
+    waiting: HashMap<EntityId, f32>,
+    flank_rotation: f32,
+}
+
+pub fn get_max_attack_tokens(wave_count: f32) -> usize {
+    2 + (wave_count / 5.0) as usize
+}
+
+impl SquadCoordinator {
+    pub fn new(max_attack_tokens: usize) -> SquadCoordinator {
+        SquadCoordinator {
+            squads: vec![],
+            tokens: vec![],
+            max_attack_tokens,
+            flank_distance: FLANK_DISTANCE,
+            waiting: HashMap::new(),
+            flank_rotation: 0.0,
+        }
+    }
+
+    /// Syncs squads with the living enemies, then expires and hands out attack tokens
+    pub fn update(&mut self, enemies: &[(EntityId, (f32, f32))], dt: f32) {
+        let positions: HashMap<EntityId, (f32, f32)> = enemies.iter().cloned().collect();
+        for squad in self.squads.iter_mut() {
+            squad.members.retain(|id| positions.contains_key(id));
+        }
+        self.squads.retain(|squad| !squad.members.is_empty());
+        self.tokens
+            .retain(|token| positions.contains_key(&token.holder));
+        self.waiting.retain(|id, _| positions.contains_key(id));
+
+        for (id, position) in enemies {
+            if self.get_squad_index(*id).is_none() {
+                self.join_squad(*id, *position, &positions);
+            }
+        }
+
+        // Spread the squads evenly around the player and slowly circle
+        self.flank_rotation += FLANK_ROTATION_SPEED * dt / 1000.0;
+        let squad_count = self.squads.len() as f32;
+        for (i, squad) in self.squads.iter_mut().enumerate() {
+            squad.flank_angle =
+                self.flank_rotation + 2.0 * std::f32::consts::PI * i as f32 / squad_count;
+        }
+
+        for token in self.tokens.iter_mut() {
+            token.remaining -= dt;
+        }
+        self.tokens.retain(|token| token.remaining > 0.0);
+        for (id, _) in enemies {
+            if !self.has_attack_token(*id) {
+                *self.waiting.entry(*id).or_insert(0.0) += dt;
+            }
+        }
+        self.hand_out_tokens();
+    }
+
+    fn join_squad(
+        &mut self,
+        id: EntityId,
+        position: (f32, f32),
+        positions: &HashMap<EntityId, (f32, f32)>,
+    ) {
+        let closest_squad = self
+            .squads
+            .iter_mut()
+            .filter(|squad| squad.members.len() < MAX_SQUAD_SIZE)
+            .map(|squad| {
+                let centre = get_squad_centre(squad, positions);
+                let distance =
+                    ((centre.0 - position.0).powi(2) + (centre.1 - position.1).powi(2)).sqrt();
+                (squad, distance)
+            })
+            .filter(|(_, distance)| *distance < SQUAD_JOIN_RADIUS)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        match closest_squad {
+            Some((squad, _)) => squad.members.push(id),
+            // the flank angle is assigned with the other squads' right after
+            None => self.squads.push(Squad {
+                members: vec![id],
+                flank_angle: 0.0,
+            }),
+        }
+    }
+
+    /// Gives free tokens to the enemies that waited longest, preferring squads
+    /// that have nobody attacking yet so the pressure rotates around the player
+    fn hand_out_tokens(&mut self) {
+        while self.tokens.len() < self.max_attack_tokens {
+            let squads_attacking: Vec<usize> = self
+                .tokens
+                .iter()
+                .filter_map(|token| self.get_squad_index(token.holder))
+                .collect();
+            let next_holder = self
+                .waiting
+                .iter()
+                .filter(|(id, _)| !self.has_attack_token(**id))
+                .max_by(|a, b| {
+                    let a_is_attacking = self
+                        .get_squad_index(*a.0)
+                        .is_some_and(|squad| squads_attacking.contains(&squad));
+                    let b_is_attacking = self
+                        .get_squad_index(*b.0)
+                        .is_some_and(|squad| squads_attacking.contains(&squad));
+                    // the map has no order, so ties go to the oldest enemy
+                    b_is_attacking
+                        .cmp(&a_is_attacking)
+                        .then(a.1.total_cmp(b.1))
+                        .then(b.0.cmp(a.0))
+                })
+                .map(|(id, _)| *id);
+            match next_holder {
+                Some(id) => {
+                    self.waiting.insert(id, 0.0);
+                    self.tokens.push(AttackToken {
+                        holder: id,
+                        remaining: ATTACK_TOKEN_DURATION,
+                    });
+                }
+                None => break,
+            }
+        }
+    }
+
+    pub fn has_attack_token(&self, id: EntityId) -> bool {
+        self.tokens.iter().any(|token| token.holder == id)
+    }
+
+    pub fn get_squad_index(&self, id: EntityId) -> Option<usize> {
+        self.squads
+            .iter()
+            .position(|squad| squad.members.contains(&id))
+    }
+
+    /// Spot around the player an enemy without a token should hold
+    pub fn get_flanking_position(
+        &self,
+        id: EntityId,
+        player_position: (f32, f32),
+    ) -> Option<(f32, f32)> {
+        let squad = &self.squads[self.get_squad_index(id)?];
+        let slot = squad.members.iter().position(|member| *member == id)? as f32;
+        let centre_slot = (squad.members.len() - 1) as f32 / 2.0;
+        let angle = squad.flank_angle + (slot - centre_slot) * FLANK_SPACING.to_radians();
+        Some((
+            player_position.0 + angle.cos() * self.flank_distance,
+            player_position.1 + angle.sin() * self.flank_distance,
+        ))
+    }
+}
+
+fn get_squad_centre(squad: &Squad, positions: &HashMap<EntityId, (f32, f32)>) -> (f32, f32) {
+    let sum = squad
+        .members
+        .iter()
+        .filter_map(|id| positions.get(id))
+        .fold((0.0, 0.0), |acc, position| {
+            (acc.0 + position.0, acc.1 + position.1)
+        });
+    let count = squad.members.len().max(1) as f32;
+    (sum.0 / count, sum.1 / count)
+}
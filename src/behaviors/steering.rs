@@ -0,0 +1,296 @@
+use rand::Rng;
+
+/// Where an enemy wants to go, resolved into a steering force every frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SteeringGoal {
+    Hold,
+    Seek((f32, f32)),
+    Arrive((f32, f32)),
+    Flee((f32, f32)),
+    Wander,
+    PursuePlayer,
+    EvadePlayer,
+}
+
+/// How strongly each force pulls on an enemy, tuned per AI personality
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SteeringWeights {
+    pub goal: f32,
+    pub separation: f32,
+    pub cohesion: f32,
+    pub alignment: f32,
+    pub wander: f32,
+    pub separation_radius: f32,
+    pub neighbor_radius: f32,
+    // units per second squared
+    pub max_acceleration: f32,
+    // radians per second
+    pub turn_rate: f32,
+}
+
+impl Default for SteeringWeights {
+    fn default() -> Self {
+        SteeringWeights {
+            goal: 1.0,
+            separation: 1.0,
+            cohesion: 0.0,
+            alignment: 0.0,
+            wander: 0.0,
+            separation_radius: 80.0,
+            neighbor_radius: 250.0,
+            max_acceleration: 1500.0,
+            turn_rate: 6.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SteeringAgent {
+    pub position: (f32, f32),
+    pub velocity: (f32, f32),
+    pub max_speed: f32,
+}
+
+fn length(v: (f32, f32)) -> f32 {
+    (v.0.powi(2) + v.1.powi(2)).sqrt()
+}
+
+fn normalize(v: (f32, f32)) -> (f32, f32) {
+    let len = length(v);
+    if len > 0.0 {
+        (v.0 / len, v.1 / len)
+    } else {
+        (0.0, 0.0)
+    }
+}
+
+fn truncate(v: (f32, f32), max: f32) -> (f32, f32) {
+    let len = length(v);
+    if len > max {
+        (v.0 / len * max, v.1 / len * max)
+    } else {
+        v
+    }
+}
+
+pub fn seek(agent: &SteeringAgent, target: (f32, f32)) -> (f32, f32) {
+    let desired = normalize((target.0 - agent.position.0, target.1 - agent.position.1));
+    (
+        desired.0 * agent.max_speed - agent.velocity.0,
+        desired.1 * agent.max_speed - agent.velocity.1,
+    )
+}
+
+/// Seek that slows down inside `slowing_radius` so the agent settles on the target
+pub fn arrive(agent: &SteeringAgent, target: (f32, f32), slowing_radius: f32) -> (f32, f32) {
+    let offset = (target.0 - agent.position.0, target.1 - agent.position.1);
+    let distance = length(offset);
+    if distance < 1.0 {
+        return (-agent.velocity.0, -agent.velocity.1);
+    }
+    let speed = agent.max_speed * (distance / slowing_radius).min(1.0);
+    let desired = normalize(offset);
+    (
+        desired.0 * speed - agent.velocity.0,
+        desired.1 * speed - agent.velocity.1,
+    )
+}
+
+pub fn flee(agent: &SteeringAgent, threat: (f32, f32)) -> (f32, f32) {
+    let desired = normalize((agent.position.0 - threat.0, agent.position.1 - threat.1));
+    (
+        desired.0 * agent.max_speed - agent.velocity.0,
+        desired.1 * agent.max_speed - agent.velocity.1,
+    )
+}
+
+/// Random jitter around the current heading
+pub fn wander(agent: &SteeringAgent) -> (f32, f32) {
+    let mut rng = rand::thread_rng();
+    let heading = match length(agent.velocity) > 0.0 {
+        true => agent.velocity.1.atan2(agent.velocity.0),
+        false => rng.gen_range(0.0..2.0 * std::f32::consts::PI),
+    };
+    let angle = heading + rng.gen_range(-0.8..0.8);
+    let target = (
+        agent.position.0 + angle.cos() * 100.0,
+        agent.position.1 + angle.sin() * 100.0,
+    );
+    seek(agent, target)
+}
+
+/// Seek toward where the target will be by the time the agent could get there
+pub fn pursuit(
+    agent: &SteeringAgent,
+    target_position: (f32, f32),
+    target_velocity: (f32, f32),
+) -> (f32, f32) {
+    let distance = length((
+        target_position.0 - agent.position.0,
+        target_position.1 - agent.position.1,
+    ));
+    let look_ahead = distance / agent.max_speed.max(1.0);
+    seek(
+        agent,
+        (
+            target_position.0 + target_velocity.0 * look_ahead,
+            target_position.1 + target_velocity.1 * look_ahead,
+        ),
+    )
+}
+
+pub fn evade(
+    agent: &SteeringAgent,
+    threat_position: (f32, f32),
+    threat_velocity: (f32, f32),
+) -> (f32, f32) {
+    let distance = length((
+        threat_position.0 - agent.position.0,
+        threat_position.1 - agent.position.1,
+    ));
+    let look_ahead = distance / agent.max_speed.max(1.0);
+    flee(
+        agent,
+        (
+            threat_position.0 + threat_velocity.0 * look_ahead,
+            threat_position.1 + threat_velocity.1 * look_ahead,
+        ),
+    )
+}
+
+/// Pushes away from neighbors closer than `radius`, harder the closer they are
+pub fn separation(agent: &SteeringAgent, neighbors: &[SteeringAgent], radius: f32) -> (f32, f32) {
+    let mut force = (0.0, 0.0);
+    for neighbor in neighbors {
+        let offset = (
+            agent.position.0 - neighbor.position.0,
+            agent.position.1 - neighbor.position.1,
+        );
+        let distance = length(offset);
+        if distance > 0.0 && distance < radius {
+            let push = (radius - distance) / radius;
+            let direction = normalize(offset);
+            force.0 += direction.0 * push;
+            force.1 += direction.1 * push;
+        }
+    }
+    (force.0 * agent.max_speed, force.1 * agent.max_speed)
+}
+
+pub fn cohesion(agent: &SteeringAgent, neighbors: &[SteeringAgent], radius: f32) -> (f32, f32) {
+    let nearby: Vec<&SteeringAgent> = neighbors
+        .iter()
+        .filter(|neighbor| {
+            length((
+                neighbor.position.0 - agent.position.0,
+                neighbor.position.1 - agent.position.1,
+            )) < radius
+        })
+        .collect();
+    if nearby.is_empty() {
+        return (0.0, 0.0);
+    }
+    let centre = nearby.iter().fold((0.0, 0.0), |acc, neighbor| {
+        (acc.0 + neighbor.position.0, acc.1 + neighbor.position.1)
+    });
+    let count = nearby.len() as f32;
+    seek(agent, (centre.0 / count, centre.1 / count))
+}
+
+pub fn alignment(agent: &SteeringAgent, neighbors: &[SteeringAgent], radius: f32) -> (f32, f32) {
+    let nearby: Vec<&SteeringAgent> = neighbors
+        .iter()
+        .filter(|neighbor| {
+            length((
+                neighbor.position.0 - agent.position.0,
+                neighbor.position.1 - agent.position.1,
+            )) < radius
+        })
+        .collect();
+    if nearby.is_empty() {
+        return (0.0, 0.0);
+    }
+    let heading = nearby.iter().fold((0.0, 0.0), |acc, neighbor| {
+        (acc.0 + neighbor.velocity.0, acc.1 + neighbor.velocity.1)
+    });
+    let count = nearby.len() as f32;
+    (
+        heading.0 / count - agent.velocity.0,
+        heading.1 / count - agent.velocity.1,
+    )
+}
+
+/// Combines the goal and flocking forces into a single weighted force
+pub fn get_steering_force(
+    agent: &SteeringAgent,
+    goal: SteeringGoal,
+    weights: &SteeringWeights,
+    neighbors: &[SteeringAgent],
+    player: &SteeringAgent,
+) -> (f32, f32) {
+    let goal_force = match goal {
+        SteeringGoal::Hold => (-agent.velocity.0, -agent.velocity.1),
+        SteeringGoal::Seek(target) => seek(agent, target),
+        SteeringGoal::Arrive(target) => arrive(agent, target, 200.0),
+        SteeringGoal::Flee(threat) => flee(agent, threat),
+        SteeringGoal::Wander => wander(agent),
+        SteeringGoal::PursuePlayer => pursuit(agent, player.position, player.velocity),
+        SteeringGoal::EvadePlayer => evade(agent, player.position, player.velocity),
+    };
+    let forces = [
+        (goal_force, weights.goal),
+        (
+            separation(agent, neighbors, weights.separation_radius),
+            weights.separation,
+        ),
+        (
+            cohesion(agent, neighbors, weights.neighbor_radius),
+            weights.cohesion,
+        ),
+        (
+            alignment(agent, neighbors, weights.neighbor_radius),
+            weights.alignment,
+        ),
+        (wander(agent), weights.wander),
+    ];
+    forces
+        .iter()
+        .filter(|(_, weight)| *weight != 0.0)
+        .fold((0.0, 0.0), |acc, (force, weight)| {
+            (acc.0 + force.0 * weight, acc.1 + force.1 * weight)
+        })
+}
+
+/// Turns a steering force into the agent's new velocity, limited by acceleration,
+/// max speed and turn rate
+pub fn apply_steering_force(
+    agent: &SteeringAgent,
+    force: (f32, f32),
+    weights: &SteeringWeights,
+    dt: f32,
+) -> (f32, f32) {
+    let acceleration = truncate(force, weights.max_acceleration);
+    let velocity = truncate(
+        (
+            agent.velocity.0 + acceleration.0 * dt,
+            agent.velocity.1 + acceleration.1 * dt,
+        ),
+        agent.max_speed,
+    );
+    let speed = length(velocity);
+    if length(agent.velocity) < 1.0 || speed < 1.0 {
+        return velocity;
+    }
+    let current_heading = agent.velocity.1.atan2(agent.velocity.0);
+    let mut turn = velocity.1.atan2(velocity.0) - current_heading;
+    // wrap the turn into -PI..PI so the agent turns the short way round
+    while turn > std::f32::consts::PI {
+        turn -= 2.0 * std::f32::consts::PI;
+    }
+    while turn < -std::f32::consts::PI {
+        turn += 2.0 * std::f32::consts::PI;
+    }
+    let max_turn = weights.turn_rate * dt;
+    let heading = current_heading + turn.clamp(-max_turn, max_turn);
+    (heading.cos() * speed, heading.sin() * speed)
+}
@@ -0,0 +1,180 @@
+use crate::behaviors::aggressive_enemy_behavior_tree::get_aggressive_enemy_behaviors;
+use crate::behaviors::elusive_enemy_behavior_tree::get_elusive_behaviors;
+use crate::behaviors::model::{Behavior, EnemyBehaviors, NodeTrait};
+use crate::behaviors::normal_enemy_behavior_tree::get_normal_behaviors;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TreeFileError {
+    #[error("failed to read behavior tree: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse behavior tree: {0}")]
+    Parse(#[from] toml::de::Error),
+}
+
+/// Structural problems found by `TreeDefinition::validate`
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TreeIssue {
+    #[error("the tree has no nodes")]
+    Empty,
+    #[error("root node {0} has a parent")]
+    RootHasParent(u32),
+    #[error("node id {0} is used more than once")]
+    DuplicateId(u32),
+    #[error("node {0} lists unknown child {1}")]
+    UnknownChild(u32, u32),
+    #[error("node {0} has unknown parent {1}")]
+    UnknownParent(u32, u32),
+    #[error("node {0} lists child {1}, whose parent is {2:?}")]
+    ParentMismatch(u32, u32, Option<u32>),
+    #[error("node {0} has parent {1}, which does not list it as a child")]
+    NotAChild(u32, u32),
+    #[error("node {0} can not be reached from the root")]
+    Unreachable(u32),
+    #[error("node {0} is its own ancestor")]
+    Cycle(u32),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TreeNodeDefinition {
+    pub id: u32,
+    pub behavior: Behavior,
+    pub parent: Option<u32>,
+    pub children: Option<(u32, u32)>,
+}
+
+/// A behavior tree as plain data, the first node is the root
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TreeDefinition {
+    pub name: String,
+    pub nodes: Vec<TreeNodeDefinition>,
+}
+
+impl TreeDefinition {
+    pub fn from_behaviors(name: &str, behaviors: EnemyBehaviors) -> TreeDefinition {
+        let nodes = std::iter::once(&behaviors.root)
+            .chain(behaviors.nodes.iter())
+            .map(|node| TreeNodeDefinition {
+                id: node.get_id(),
+                behavior: node.get_name(),
+                parent: node.get_parent(),
+                children: node.get_children(),
+            })
+            .collect();
+        TreeDefinition {
+            name: name.to_string(),
+            nodes,
+        }
+    }
+
+    fn get_node(&self, id: u32) -> Option<&TreeNodeDefinition> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    /// Every structural problem in the tree, empty when it is valid
+    pub fn validate(&self) -> Vec<TreeIssue> {
+        let mut issues = vec![];
+        let root = match self.nodes.first() {
+            Some(root) => root,
+            None => return vec![TreeIssue::Empty],
+        };
+        if root.parent.is_some() {
+            issues.push(TreeIssue::RootHasParent(root.id));
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            if self.nodes[..i].iter().any(|other| other.id == node.id) {
+                issues.push(TreeIssue::DuplicateId(node.id));
+            }
+            if let Some((left, right)) = node.children {
+                for child_id in [left, right] {
+                    match self.get_node(child_id) {
+                        Some(child) if child.parent != Some(node.id) => {
+                            issues.push(TreeIssue::ParentMismatch(node.id, child_id, child.parent))
+                        }
+                        Some(_) => (),
+                        None => issues.push(TreeIssue::UnknownChild(node.id, child_id)),
+                    }
+                }
+            }
+            if let Some(parent_id) = node.parent {
+                match self.get_node(parent_id) {
+                    Some(parent) => {
+                        let is_listed = parent
+                            .children
+                            .is_some_and(|(left, right)| left == node.id || right == node.id);
+                        if !is_listed {
+                            issues.push(TreeIssue::NotAChild(node.id, parent_id));
+                        }
+                    }
+                    None => issues.push(TreeIssue::UnknownParent(node.id, parent_id)),
+                }
+            }
+        }
+
+        // Walk down from the root, a node seen twice on the same path closes a cycle
+        let mut reached = vec![];
+        let mut stack = vec![(root.id, vec![])];
+        while let Some((id, mut path)) = stack.pop() {
+            if path.contains(&id) {
+                if !issues.contains(&TreeIssue::Cycle(id)) {
+                    issues.push(TreeIssue::Cycle(id));
+                }
+                continue;
+            }
+            if !reached.contains(&id) {
+                reached.push(id);
+            }
+            path.push(id);
+            if let Some((left, right)) = self.get_node(id).and_then(|node| node.children) {
+                for child_id in [left, right] {
+                    if self.get_node(child_id).is_some() {
+                        stack.push((child_id, path.clone()));
+                    }
+                }
+            }
+        }
+        for node in &self.nodes {
+            if !reached.contains(&node.id) {
+                issues.push(TreeIssue::Unreachable(node.id));
+            }
+        }
+        issues
+    }
+
+    /// Graphviz DOT source, render it with `dot -Tsvg`
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        let _ = writeln!(dot, "digraph \"{}\" {{", self.name);
+        let _ = writeln!(dot, "    node [shape=box, fontname=\"monospace\"];");
+        for node in &self.nodes {
+            let _ = writeln!(
+                dot,
+                "    n{} [label=\"#{} {:?}\"];",
+                node.id, node.id, node.behavior
+            );
+        }
+        for node in &self.nodes {
+            if let Some((left, right)) = node.children {
+                let _ = writeln!(dot, "    n{} -> n{};", node.id, left);
+                let _ = writeln!(dot, "    n{} -> n{};", node.id, right);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+pub fn get_builtin_trees() -> Vec<TreeDefinition> {
+    vec![
+        TreeDefinition::from_behaviors("normal", get_normal_behaviors()),
+        TreeDefinition::from_behaviors("aggressive", get_aggressive_enemy_behaviors()),
+        TreeDefinition::from_behaviors("elusive", get_elusive_behaviors()),
+    ]
+}
+
+pub fn load_tree_definition(path: &str) -> Result<TreeDefinition, TreeFileError> {
+    let source = std::fs::read_to_string(path)?;
+    Ok(toml::from_str(&source)?)
+}
@@ -0,0 +1,7 @@
+use gg_ez_test_02::environment;
+use std::env;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    std::process::exit(environment::run(&args));
+}
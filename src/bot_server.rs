@@ -0,0 +1,297 @@
+use crate::actors::models::EntityId;
+use crate::autopilot::BotInput;
+use ggez::event::MouseButton;
+use ggez::input::keyboard::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+use thiserror::Error;
+
+pub const DEFAULT_BOT_PORT: u16 = 7878;
+// how long lockstep waits for a client before dropping it
+const LOCKSTEP_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Error)]
+pub enum BotServerError {
+    #[error("bot connection failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("bad bot message: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("the bot did not answer in time")]
+    Timeout,
+    #[error("the bot disconnected")]
+    Disconnected,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BotMode {
+    // the game waits for the client's input every tick and runs at a fixed time step
+    Lockstep,
+    // the game keeps running and applies whatever input has arrived
+    Realtime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerSnapshot {
+    pub x: f32,
+    pub y: f32,
+    pub hp: f32,
+    pub shield: f32,
+    pub lives: u32,
+    pub alt_cd: f32,
+    pub dash_cd: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EnemySnapshot {
+    pub id: EntityId,
+    pub kind: String,
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub hp: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectileSnapshot {
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    // "player" or "enemy"
+    pub faction: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WaveSnapshot {
+    pub number: u32,
+    pub enemies_remaining: usize,
+    pub is_boss_wave: bool,
+}
+
+/// Sent to the client as one JSON line at the start of every tick
+#[derive(Debug, Clone, Serialize)]
+pub struct Snapshot {
+    pub tick: u64,
+    // "main_menu" or "main_game"
+    pub state: &'static str,
+    // ms the tick covers
+    pub dt: f32,
+    pub player: PlayerSnapshot,
+    pub enemies: Vec<EnemySnapshot>,
+    pub projectiles: Vec<ProjectileSnapshot>,
+    pub wave: WaveSnapshot,
+    pub kills: u64,
+    pub score: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientCommand {
+    KeyDown { key: String },
+    KeyUp { key: String },
+    MouseDown { button: String, x: f32, y: f32 },
+}
+
+/// One JSON line from the client, the input for a tick
+#[derive(Debug, Clone, Deserialize)]
+struct ClientMessage {
+    #[serde(default)]
+    commands: Vec<ClientCommand>,
+}
+
+struct BotClient {
+    stream: TcpStream,
+    // bytes received after the last complete line
+    buffer: Vec<u8>,
+}
+
+/// Lets bots outside the crate play over a localhost socket with line-delimited JSON:
+/// the game sends a `Snapshot` every tick and the client answers with input commands that
+/// are injected like keyboard and mouse events. One client is served at a time.
+pub struct BotServer {
+    pub mode: BotMode,
+    listener: TcpListener,
+    client: Option<BotClient>,
+    tick: u64,
+}
+
+impl BotServer {
+    pub fn bind(port: u16, mode: BotMode) -> Result<BotServer, BotServerError> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        listener.set_nonblocking(true)?;
+        println!("Waiting for a bot on 127.0.0.1:{} ({:?})", port, mode);
+        Ok(BotServer {
+            mode,
+            listener,
+            client: None,
+            tick: 0,
+        })
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.client.is_some()
+    }
+
+    pub fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    /// Sends the snapshot and returns the client's input for this tick. In lockstep mode this
+    /// blocks until the client answers. A client that errors is dropped so another can connect.
+    /// `dash_key` is whatever the player has bound the dash to.
+    pub fn exchange(&mut self, snapshot: &Snapshot, dash_key: KeyCode) -> Vec<BotInput> {
+        if self.client.is_none() {
+            self.accept();
+        }
+        let mode = self.mode;
+        let client = match &mut self.client {
+            Some(client) => client,
+            None => return vec![],
+        };
+        let res = client.send(snapshot).and_then(|_| match mode {
+            BotMode::Lockstep => client.receive_line().map(|line| vec![line]),
+            BotMode::Realtime => client.receive_available(),
+        });
+        let lines = match res {
+            Ok(lines) => lines,
+            Err(e) => {
+                println!("Dropping bot: {}", e);
+                self.client = None;
+                return vec![];
+            }
+        };
+        let mut inputs = vec![];
+        for line in lines {
+            match serde_json::from_slice::<ClientMessage>(&line) {
+                Ok(message) => inputs.extend(
+                    message
+                        .commands
+                        .iter()
+                        .filter_map(|command| get_bot_input(command, dash_key)),
+                ),
+                Err(e) => println!("Ignoring bot message: {}", BotServerError::from(e)),
+            }
+        }
+        inputs
+    }
+
+    fn accept(&mut self) {
+        let stream = match self.listener.accept() {
+            Ok((stream, address)) => {
+                println!("Bot connected from {}", address);
+                stream
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+            Err(e) => {
+                println!("Error accepting bot: {:?}", e);
+                return;
+            }
+        };
+        let res = stream.set_nodelay(true).and_then(|_| match self.mode {
+            BotMode::Lockstep => stream
+                .set_nonblocking(false)
+                .and_then(|_| stream.set_read_timeout(Some(LOCKSTEP_TIMEOUT))),
+            BotMode::Realtime => stream.set_nonblocking(true),
+        });
+        match res {
+            Ok(_) => {
+                self.client = Some(BotClient {
+                    stream,
+                    buffer: vec![],
+                })
+            }
+            Err(e) => println!("Error setting up bot connection: {:?}", e),
+        }
+    }
+}
+
+impl BotClient {
+    fn send(&mut self, snapshot: &Snapshot) -> Result<(), BotServerError> {
+        let mut line = serde_json::to_vec(snapshot)?;
+        line.push(b'\n');
+        self.stream.write_all(&line)?;
+        Ok(())
+    }
+
+    fn take_line(&mut self) -> Option<Vec<u8>> {
+        let end = self.buffer.iter().position(|byte| *byte == b'\n')?;
+        let mut line: Vec<u8> = self.buffer.drain(..=end).collect();
+        line.pop();
+        Some(line)
+    }
+
+    /// Blocks until a whole line has arrived
+    fn receive_line(&mut self) -> Result<Vec<u8>, BotServerError> {
+        let mut chunk = [0; 4096];
+        loop {
+            if let Some(line) = self.take_line() {
+                return Ok(line);
+            }
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(BotServerError::Disconnected),
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                    return Err(BotServerError::Timeout)
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Every whole line received so far, without waiting
+    fn receive_available(&mut self) -> Result<Vec<Vec<u8>>, BotServerError> {
+        let mut chunk = [0; 4096];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err(BotServerError::Disconnected),
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let mut lines = vec![];
+        while let Some(line) = self.take_line() {
+            lines.push(line);
+        }
+        Ok(lines)
+    }
+}
+
+fn get_bot_input(command: &ClientCommand, dash_key: KeyCode) -> Option<BotInput> {
+    match command {
+        ClientCommand::KeyDown { key } => parse_key(key, dash_key).map(BotInput::KeyDown),
+        ClientCommand::KeyUp { key } => parse_key(key, dash_key).map(BotInput::KeyUp),
+        ClientCommand::MouseDown { button, x, y } => {
+            let button = match button.as_str() {
+                "left" => MouseButton::Left,
+                "right" => MouseButton::Right,
+                _ => return None,
+            };
+            Some(BotInput::MouseDown(button, *x, *y))
+        }
+    }
+}
+
+/// Keys a bot may press, debug and quit keys stay with the person at the keyboard. `Dash`
+/// presses whatever the dash is bound to, and so does the bound key's own name.
+fn parse_key(name: &str, dash_key: KeyCode) -> Option<KeyCode> {
+    match name {
+        "Dash" => Some(dash_key),
+        _ if name == format!("{:?}", dash_key) => Some(dash_key),
+        "W" => Some(KeyCode::W),
+        "A" => Some(KeyCode::A),
+        "S" => Some(KeyCode::S),
+        "D" => Some(KeyCode::D),
+        "LShift" => Some(KeyCode::LShift),
+        "Space" => Some(KeyCode::Space),
+        "Return" => Some(KeyCode::Return),
+        "Key1" => Some(KeyCode::Key1),
+        "Key2" => Some(KeyCode::Key2),
+        "Key3" => Some(KeyCode::Key3),
+        _ => None,
+    }
+}
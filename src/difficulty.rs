@@ -0,0 +1,59 @@
+use crate::actors::player::PlayerSettings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    /// Cap on how hard adaptive enemies counter the player's habits, see adaptation.rs
+    pub fn max_adaptation(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.3,
+            Difficulty::Normal => 0.6,
+            Difficulty::Hard => 1.0,
+        }
+    }
+
+    pub fn player_settings(&self) -> PlayerSettings {
+        match self {
+            Difficulty::Easy => PlayerSettings {
+                max_hp: 150.0,
+                max_shield: 75.0,
+                shield_recharge_delay: 2000.0,
+                shield_recharge_rate: 25.0,
+                lives: 5,
+                respawn_invulnerability: 3000.0,
+                respawn_clear_radius: 600.0,
+            },
+            Difficulty::Normal => PlayerSettings {
+                max_hp: 100.0,
+                max_shield: 50.0,
+                shield_recharge_delay: 3000.0,
+                shield_recharge_rate: 15.0,
+                lives: 3,
+                respawn_invulnerability: 2000.0,
+                respawn_clear_radius: 400.0,
+            },
+            Difficulty::Hard => PlayerSettings {
+                max_hp: 100.0,
+                max_shield: 25.0,
+                shield_recharge_delay: 5000.0,
+                shield_recharge_rate: 10.0,
+                lives: 1,
+                respawn_invulnerability: 1500.0,
+                respawn_clear_radius: 250.0,
+            },
+        }
+    }
+}
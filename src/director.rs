@@ -0,0 +1,235 @@
+use serde::Deserialize;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DirectorError {
+    #[error("failed to open director settings: {0}")]
+    Open(#[from] ggez::GameError),
+    #[error("failed to read director settings: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse director settings: {0}")]
+    Parse(#[from] toml::de::Error),
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct DirectorBounds {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl DirectorBounds {
+    fn lerp(&self, t: f32) -> f32 {
+        self.min + (self.max - self.min) * t.clamp(0.0, 1.0)
+    }
+}
+
+/// Designer-set limits for the director, loaded from resources/director.toml
+#[derive(Debug, Clone, Deserialize)]
+pub struct DirectorSettings {
+    // all durations are in ms
+    pub evaluation_interval: f32,
+    pub window: f32,
+    pub log_path: String,
+    pub damage_rate_ceiling: f32,
+    pub kill_rate_ceiling: f32,
+    pub peak_stress: f32,
+    pub calm_duration: f32,
+    pub build_up_duration: f32,
+    pub wave_size_scale: DirectorBounds,
+    pub wave_delay: DirectorBounds,
+    pub aggressive_share: DirectorBounds,
+    pub attack_token_offset: DirectorBounds,
+    pub pickup_drop_chance: DirectorBounds,
+}
+
+pub fn load_director_settings(
+    ctx: &ggez::Context,
+    path: &str,
+) -> Result<DirectorSettings, DirectorError> {
+    let mut source = String::new();
+    ctx.fs.open(path)?.read_to_string(&mut source)?;
+    Ok(toml::from_str(&source)?)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DirectorMode {
+    BuildUp,
+    // ms left before the pressure starts building again
+    Calm(f32),
+}
+
+/// The values the director hands to the game, picked from the settings bounds
+#[derive(Debug, Clone, Copy)]
+pub struct DirectorKnobs {
+    pub wave_size_scale: f32,
+    pub wave_delay: f32,
+    pub aggressive_share: f32,
+    pub attack_token_offset: i32,
+    pub pickup_drop_chance: f32,
+}
+
+/// Watches how the player is doing and tunes the pacing to match
+pub struct Director {
+    settings: DirectorSettings,
+    pub mode: DirectorMode,
+    pub knobs: DirectorKnobs,
+    elapsed: f32,
+    since_evaluation: f32,
+    build_up_elapsed: f32,
+    // timestamps in ms of recent events, damage also keeps the amount
+    damage_taken: Vec<(f32, f32)>,
+    kills: Vec<f32>,
+    shots: Vec<f32>,
+    hits: Vec<f32>,
+    log: Option<File>,
+}
+
+impl Director {
+    pub fn new(settings: DirectorSettings) -> Director {
+        // Every run gets a director, so append to keep the earlier runs in the log
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&settings.log_path);
+        let log = match log {
+            Ok(mut file) => {
+                if let Err(e) = writeln!(file, "--- new run ---") {
+                    println!("Error writing director log: {:?}", e);
+                }
+                Some(file)
+            }
+            Err(e) => {
+                println!("Error opening director log: {:?}", e);
+                None
+            }
+        };
+        let mut director = Director {
+            knobs: DirectorKnobs {
+                wave_size_scale: 1.0,
+                wave_delay: 0.0,
+                aggressive_share: 0.0,
+                attack_token_offset: 0,
+                pickup_drop_chance: 0.0,
+            },
+            settings,
+            mode: DirectorMode::BuildUp,
+            elapsed: 0.0,
+            since_evaluation: 0.0,
+            build_up_elapsed: 0.0,
+            damage_taken: vec![],
+            kills: vec![],
+            shots: vec![],
+            hits: vec![],
+            log,
+        };
+        director.set_knobs(0.5);
+        director
+    }
+
+    pub fn record_damage_taken(&mut self, amount: f32) {
+        self.damage_taken.push((self.elapsed, amount));
+    }
+
+    pub fn record_kill(&mut self) {
+        self.kills.push(self.elapsed);
+    }
+
+    pub fn record_shots(&mut self, count: usize) {
+        for _ in 0..count {
+            self.shots.push(self.elapsed);
+        }
+    }
+
+    pub fn record_hit(&mut self) {
+        self.hits.push(self.elapsed);
+    }
+
+    fn set_knobs(&mut self, pressure: f32) {
+        let settings = &self.settings;
+        self.knobs = DirectorKnobs {
+            wave_size_scale: settings.wave_size_scale.lerp(pressure),
+            wave_delay: settings.wave_delay.lerp(1.0 - pressure),
+            aggressive_share: settings.aggressive_share.lerp(pressure),
+            attack_token_offset: settings.attack_token_offset.lerp(pressure).round() as i32,
+            pickup_drop_chance: settings.pickup_drop_chance.lerp(1.0 - pressure),
+        };
+    }
+
+    /// Advances the director by `dt` ms and re-tunes the knobs once per evaluation interval
+    pub fn update(&mut self, dt: f32, hp_ratio: f32) {
+        self.elapsed += dt;
+        self.since_evaluation += dt;
+        if self.since_evaluation < self.settings.evaluation_interval {
+            return;
+        }
+        let interval = self.since_evaluation;
+        self.since_evaluation = 0.0;
+
+        let window_start = self.elapsed - self.settings.window;
+        self.damage_taken.retain(|(time, _)| *time >= window_start);
+        self.kills.retain(|time| *time >= window_start);
+        self.shots.retain(|time| *time >= window_start);
+        self.hits.retain(|time| *time >= window_start);
+
+        let window_secs = self.settings.window.min(self.elapsed) / 1000.0;
+        let damage_rate = self
+            .damage_taken
+            .iter()
+            .map(|(_, amount)| amount)
+            .sum::<f32>()
+            / window_secs;
+        let kill_rate = self.kills.len() as f32 / window_secs;
+        let accuracy = match self.shots.is_empty() {
+            true => 0.5,
+            false => (self.hits.len() as f32 / self.shots.len() as f32).min(1.0),
+        };
+        let stress = 0.5 * (1.0 - hp_ratio.clamp(0.0, 1.0))
+            + 0.5 * (damage_rate / self.settings.damage_rate_ceiling).min(1.0);
+        let skill = 0.5 * (kill_rate / self.settings.kill_rate_ceiling).min(1.0) + 0.5 * accuracy;
+
+        let pressure = match self.mode {
+            DirectorMode::Calm(remaining) => {
+                let remaining = remaining - interval;
+                self.mode = match remaining > 0.0 {
+                    true => DirectorMode::Calm(remaining),
+                    false => {
+                        self.build_up_elapsed = 0.0;
+                        DirectorMode::BuildUp
+                    }
+                };
+                0.0
+            }
+            DirectorMode::BuildUp if stress >= self.settings.peak_stress => {
+                self.mode = DirectorMode::Calm(self.settings.calm_duration);
+                0.0
+            }
+            DirectorMode::BuildUp => {
+                self.build_up_elapsed += interval;
+                let ramp = (self.build_up_elapsed / self.settings.build_up_duration).min(1.0);
+                ((skill + ramp) / 2.0 * (1.0 - stress)).clamp(0.0, 1.0)
+            }
+        };
+        self.set_knobs(pressure);
+
+        let line = format!(
+            "t={:.1}s mode={:?} hp={:.2} damage/s={:.2} kills/s={:.2} accuracy={:.2} stress={:.2} skill={:.2} pressure={:.2} -> {:?}",
+            self.elapsed / 1000.0,
+            self.mode,
+            hp_ratio,
+            damage_rate,
+            kill_rate,
+            accuracy,
+            stress,
+            skill,
+            pressure,
+            self.knobs,
+        );
+        if let Some(log) = &mut self.log {
+            if let Err(e) = writeln!(log, "{}", line) {
+                println!("Error writing director log: {:?}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Stable handle for an actor, unlike its index it survives other actors being removed
+pub type EntityId = u64;
+
+static NEXT_ENTITY_ID: AtomicU64 = AtomicU64::new(1);
+
+pub fn next_entity_id() -> EntityId {
+    NEXT_ENTITY_ID.fetch_add(1, Ordering::Relaxed)
+}
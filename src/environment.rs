@@ -0,0 +1,563 @@
+use crate::behaviors::enemy_ai::aggressive_enemy_ai::AggressiveEnemyAI;
+use crate::behaviors::enemy_ai::elusive_enemy_ai::ElusiveEnemyAI;
+use crate::behaviors::enemy_ai::model::EnemyAi;
+use crate::behaviors::enemy_ai::normal_enemy_ai::NormalEnemyAI;
+use crate::behaviors::enemy_ai::utility_enemy_ai::UtilityEnemyAI;
+use crate::behaviors::enemy_ai::world_view::{AiCommand, Faction, WeaponId, WorldView};
+use crate::behaviors::steering::SteeringGoal;
+use crate::entity::EntityId;
+use crate::simulation::{PlayerInput, ScriptedPlayer, SimEvent, SimSettings, Simulation};
+use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Instant;
+use thiserror::Error;
+
+const USAGE: &str = "usage: gym [--role player|enemy] [--opponent normal|aggressive|elusive|utility] [--episodes <n>] [--seed <n>] [--rewards <file.toml>]";
+const SELF_FEATURES: usize = 6;
+const TARGET_FEATURES: usize = 6;
+const THREAT_FEATURES: usize = 5;
+
+#[derive(Debug, Error)]
+pub enum EnvironmentError {
+    #[error("failed to read reward shaping: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse reward shaping: {0}")]
+    Parse(#[from] toml::de::Error),
+}
+
+/// Which side of the fight the agent plays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgentRole {
+    Player,
+    // the first enemy, the rest keep their hand-written AI and a scripted player fights them
+    Enemy,
+}
+
+/// Hand-written AI the agent fights against, or fights alongside when playing an enemy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpponentAi {
+    Normal,
+    Aggressive,
+    Elusive,
+    Utility,
+}
+
+impl OpponentAi {
+    pub fn from_name(name: &str) -> Option<OpponentAi> {
+        match name {
+            "normal" => Some(OpponentAi::Normal),
+            "aggressive" => Some(OpponentAi::Aggressive),
+            "elusive" => Some(OpponentAi::Elusive),
+            "utility" => Some(OpponentAi::Utility),
+            _ => None,
+        }
+    }
+
+    fn create(&self) -> Box<dyn EnemyAi> {
+        match self {
+            OpponentAi::Normal => Box::new(NormalEnemyAI::new()),
+            OpponentAi::Aggressive => Box::new(AggressiveEnemyAI::new()),
+            OpponentAi::Elusive => Box::new(ElusiveEnemyAI::new()),
+            OpponentAi::Utility => Box::new(UtilityEnemyAI::new()),
+        }
+    }
+}
+
+/// Reward for each thing that can happen to the agent, all of them can be tuned or zeroed
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RewardShaping {
+    // per point of damage
+    pub damage_dealt: f32,
+    pub damage_taken: f32,
+    // per enemy killed while playing the player
+    pub kill: f32,
+    pub death: f32,
+    // the player clearing every enemy, or the player dying while playing an enemy
+    pub win: f32,
+    // per second alive
+    pub survival: f32,
+    // per shot fired, usually a small cost
+    pub shot: f32,
+}
+
+impl Default for RewardShaping {
+    fn default() -> Self {
+        RewardShaping {
+            damage_dealt: 0.1,
+            damage_taken: 0.1,
+            kill: 1.0,
+            death: 5.0,
+            win: 5.0,
+            survival: 0.0,
+            shot: 0.01,
+        }
+    }
+}
+
+pub fn load_reward_shaping(path: &str) -> Result<RewardShaping, EnvironmentError> {
+    let source = std::fs::read_to_string(path)?;
+    Ok(toml::from_str(&source)?)
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvSettings {
+    pub sim: SimSettings,
+    pub role: AgentRole,
+    pub opponent: OpponentAi,
+    pub rewards: RewardShaping,
+    // simulation steps each action is held for
+    pub frame_skip: usize,
+    // nearest enemies (or the player) in the observation
+    pub max_targets: usize,
+    // nearest hostile projectiles in the observation
+    pub max_threats: usize,
+    // skill of the scripted player when the agent is an enemy
+    pub player_skill: f32,
+}
+
+impl Default for EnvSettings {
+    fn default() -> Self {
+        EnvSettings {
+            sim: SimSettings::default(),
+            role: AgentRole::Player,
+            opponent: OpponentAi::Normal,
+            rewards: RewardShaping::default(),
+            frame_skip: 4,
+            max_targets: 4,
+            max_threats: 8,
+            player_skill: 0.6,
+        }
+    }
+}
+
+/// What the agent does for one step, directions are relative to the agent and get normalized
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Action {
+    pub movement: (f32, f32),
+    pub aim: (f32, f32),
+    pub is_firing: bool,
+}
+
+impl Action {
+    pub const SIZE: usize = 5;
+
+    /// `[move x, move y, aim x, aim y, fire]`, fire counts when above zero
+    pub fn from_vector(values: &[f32]) -> Action {
+        let get = |i: usize| values.get(i).copied().unwrap_or(0.0);
+        Action {
+            movement: (get(0), get(1)),
+            aim: (get(2), get(3)),
+            is_firing: get(4) > 0.0,
+        }
+    }
+}
+
+/// The agent's own ship, while it is alive
+struct AgentState {
+    position: (f32, f32),
+    velocity: (f32, f32),
+    max_speed: f32,
+    hp: f32,
+    is_weapon_ready: bool,
+}
+
+/// Something the agent is fighting
+struct TargetState {
+    position: (f32, f32),
+    velocity: (f32, f32),
+    max_speed: f32,
+    hp_fraction: f32,
+}
+
+/// Replays whatever the environment was last told to do, stands in for the agent's enemy
+struct ExternalEnemyAI {
+    commands: Rc<RefCell<Vec<AiCommand>>>,
+}
+
+impl EnemyAi for ExternalEnemyAI {
+    fn new() -> Self {
+        ExternalEnemyAI {
+            commands: Rc::new(RefCell::new(vec![])),
+        }
+    }
+
+    fn perform_action(&mut self, _world: &WorldView) -> Result<Vec<AiCommand>> {
+        Ok(self.commands.borrow_mut().drain(..).collect())
+    }
+}
+
+/// Gym-style wrapper around the headless simulation for training agents offline:
+/// `reset(seed)` starts a fight, `step(action)` returns `(observation, reward, done)`.
+/// Observations are fixed-length vectors of the agent itself, its nearest targets and the
+/// nearest hostile projectiles, with positions relative to the agent and scaled to about -1..1.
+pub struct Environment {
+    pub settings: EnvSettings,
+    pub sim: Simulation,
+    agent_id: EntityId,
+    scripted_player: ScriptedPlayer,
+    enemy_commands: Rc<RefCell<Vec<AiCommand>>>,
+    is_done: bool,
+}
+
+impl Environment {
+    pub fn new(settings: EnvSettings) -> Environment {
+        let sim = Simulation::new(settings.sim.clone(), 0);
+        Environment {
+            agent_id: sim.player.id,
+            scripted_player: ScriptedPlayer::new(settings.player_skill, 0),
+            sim,
+            settings,
+            enemy_commands: Rc::new(RefCell::new(vec![])),
+            is_done: true,
+        }
+    }
+
+    pub fn observation_size(&self) -> usize {
+        SELF_FEATURES
+            + self.settings.max_targets * TARGET_FEATURES
+            + self.settings.max_threats * THREAT_FEATURES
+    }
+
+    /// Starts a new fight. The seed sets the spawn points, enemy aim and the scripted player,
+    /// the hand-written AIs still roll their own timings.
+    pub fn reset(&mut self, seed: u64) -> Vec<f32> {
+        self.sim = Simulation::new(self.settings.sim.clone(), seed);
+        self.scripted_player = ScriptedPlayer::new(self.settings.player_skill, seed);
+        self.agent_id = self.sim.player.id;
+        for i in 0..self.settings.sim.enemy_count {
+            match (self.settings.role, i) {
+                (AgentRole::Enemy, 0) => {
+                    let ai = ExternalEnemyAI::new();
+                    self.enemy_commands = ai.commands.clone();
+                    self.sim.spawn_enemy(Box::new(ai));
+                    self.agent_id = self.sim.enemies[0].actor.id;
+                }
+                _ => self.sim.spawn_enemy(self.settings.opponent.create()),
+            }
+        }
+        self.is_done = false;
+        self.get_observation()
+    }
+
+    pub fn step(&mut self, action: Action) -> (Vec<f32>, f32, bool) {
+        if self.is_done {
+            return (self.get_observation(), 0.0, true);
+        }
+        let mut reward = 0.0;
+        for _ in 0..self.settings.frame_skip.max(1) {
+            let input = match self.settings.role {
+                AgentRole::Player => {
+                    let position = self.sim.player.position;
+                    PlayerInput {
+                        movement: action.movement,
+                        aim: (position.0 + action.aim.0, position.1 + action.aim.1),
+                        is_firing: action.is_firing,
+                    }
+                }
+                AgentRole::Enemy => {
+                    self.set_enemy_commands(action);
+                    self.scripted_player.get_input(&self.sim)
+                }
+            };
+            self.sim.step(input);
+            reward += self.get_reward();
+            self.is_done = self.sim.is_done() || self.get_agent().is_none();
+            if self.is_done {
+                break;
+            }
+        }
+        (self.get_observation(), reward, self.is_done)
+    }
+
+    fn set_enemy_commands(&mut self, action: Action) {
+        let position = match self.get_agent() {
+            Some(agent) => agent.position,
+            None => return,
+        };
+        let length = (action.movement.0.powi(2) + action.movement.1.powi(2)).sqrt();
+        let goal = match length > 0.1 {
+            true => SteeringGoal::Seek((
+                position.0 + action.movement.0 / length * 200.0,
+                position.1 + action.movement.1 / length * 200.0,
+            )),
+            false => SteeringGoal::Hold,
+        };
+        let mut commands = self.enemy_commands.borrow_mut();
+        commands.clear();
+        commands.push(AiCommand::Move(goal));
+        commands.push(AiCommand::Aim((
+            position.0 + action.aim.0,
+            position.1 + action.aim.1,
+        )));
+        if action.is_firing {
+            commands.push(AiCommand::Fire(WeaponId::Blaster));
+        }
+    }
+
+    fn get_agent(&self) -> Option<AgentState> {
+        match self.settings.role {
+            AgentRole::Player => {
+                let player = &self.sim.player;
+                match player.hp > 0.0 {
+                    true => Some(AgentState {
+                        position: player.position,
+                        velocity: player.velocity,
+                        max_speed: player.max_speed,
+                        hp: player.hp,
+                        is_weapon_ready: self.sim.is_player_weapon_ready(),
+                    }),
+                    false => None,
+                }
+            }
+            AgentRole::Enemy => self
+                .sim
+                .enemies
+                .iter()
+                .find(|enemy| enemy.actor.id == self.agent_id)
+                .map(|enemy| {
+                    let actor = &enemy.actor;
+                    AgentState {
+                        position: actor.position,
+                        velocity: actor.velocity,
+                        max_speed: actor.max_speed,
+                        hp: actor.hp,
+                        is_weapon_ready: actor.attack_cooldown <= 0.0,
+                    }
+                }),
+        }
+    }
+
+    fn get_reward(&self) -> f32 {
+        let rewards = &self.settings.rewards;
+        let player_id = self.sim.player.id;
+        let mut reward = 0.0;
+        for event in &self.sim.events {
+            match *event {
+                SimEvent::Shot(owner) if owner == self.agent_id => reward -= rewards.shot,
+                SimEvent::Hit { owner, damage, .. } if owner == self.agent_id => {
+                    reward += rewards.damage_dealt * damage
+                }
+                SimEvent::Hit { target, damage, .. } if target == self.agent_id => {
+                    reward -= rewards.damage_taken * damage
+                }
+                SimEvent::Killed(id) if id == self.agent_id => reward -= rewards.death,
+                SimEvent::Killed(id) => match self.settings.role {
+                    AgentRole::Player => reward += rewards.kill,
+                    AgentRole::Enemy if id == player_id => reward += rewards.win,
+                    AgentRole::Enemy => (),
+                },
+                _ => (),
+            }
+        }
+        if self.get_agent().is_some() {
+            reward += rewards.survival * self.settings.sim.dt / 1000.0;
+            if self.settings.role == AgentRole::Player && self.sim.enemies.is_empty() {
+                reward += rewards.win;
+            }
+        }
+        reward
+    }
+
+    pub fn get_observation(&self) -> Vec<f32> {
+        let arena = self.settings.sim.arena;
+        let mut observation = Vec::with_capacity(self.observation_size());
+        let AgentState {
+            position,
+            velocity,
+            max_speed,
+            hp,
+            is_weapon_ready,
+        } = self.get_agent().unwrap_or(AgentState {
+            position: (0.0, 0.0),
+            velocity: (0.0, 0.0),
+            max_speed: 1.0,
+            hp: 0.0,
+            is_weapon_ready: false,
+        });
+        let max_hp = match self.settings.role {
+            AgentRole::Player => self.settings.sim.player_hp,
+            AgentRole::Enemy => self.settings.sim.enemy_hp,
+        };
+        let max_speed = max_speed.max(1.0);
+        observation.extend([
+            position.0 / arena.0,
+            position.1 / arena.1,
+            velocity.0 / max_speed,
+            velocity.1 / max_speed,
+            hp / max_hp,
+            match is_weapon_ready {
+                true => 1.0,
+                false => 0.0,
+            },
+        ]);
+
+        let mut targets: Vec<TargetState> = match self.settings.role {
+            AgentRole::Player => self
+                .sim
+                .enemies
+                .iter()
+                .map(|enemy| {
+                    let actor = &enemy.actor;
+                    TargetState {
+                        position: actor.position,
+                        velocity: actor.velocity,
+                        max_speed: actor.max_speed,
+                        hp_fraction: actor.hp / self.settings.sim.enemy_hp,
+                    }
+                })
+                .collect(),
+            AgentRole::Enemy => {
+                let player = &self.sim.player;
+                vec![TargetState {
+                    position: player.position,
+                    velocity: player.velocity,
+                    max_speed: player.max_speed,
+                    hp_fraction: player.hp / self.settings.sim.player_hp,
+                }]
+            }
+        };
+        let distance =
+            |other: (f32, f32)| (other.0 - position.0).powi(2) + (other.1 - position.1).powi(2);
+        targets.sort_by(|a, b| distance(a.position).total_cmp(&distance(b.position)));
+        for i in 0..self.settings.max_targets {
+            match targets.get(i) {
+                Some(target) => {
+                    let target_speed = target.max_speed.max(1.0);
+                    observation.extend([
+                        1.0,
+                        (target.position.0 - position.0) / arena.0,
+                        (target.position.1 - position.1) / arena.1,
+                        target.velocity.0 / target_speed,
+                        target.velocity.1 / target_speed,
+                        target.hp_fraction,
+                    ]);
+                }
+                None => observation.extend([0.0; TARGET_FEATURES]),
+            }
+        }
+
+        let hostile = match self.settings.role {
+            AgentRole::Player => Faction::Enemy,
+            AgentRole::Enemy => Faction::Player,
+        };
+        let mut threats: Vec<_> = self
+            .sim
+            .projectiles
+            .iter()
+            .filter(|projectile| projectile.faction == hostile)
+            .collect();
+        threats.sort_by(|a, b| distance(a.position).total_cmp(&distance(b.position)));
+        let projectile_speed = self.settings.sim.projectile_speed.max(1.0);
+        for i in 0..self.settings.max_threats {
+            match threats.get(i) {
+                Some(projectile) => observation.extend([
+                    1.0,
+                    (projectile.position.0 - position.0) / arena.0,
+                    (projectile.position.1 - position.1) / arena.1,
+                    projectile.velocity.0 / projectile_speed,
+                    projectile.velocity.1 / projectile_speed,
+                ]),
+                None => observation.extend([0.0; THREAT_FEATURES]),
+            }
+        }
+        observation
+    }
+}
+
+/// Parses the `gym` command line and plays episodes with random actions, printing the return
+/// of each one and how many steps per second the environment manages
+pub fn run(args: &[String]) -> i32 {
+    let mut settings = EnvSettings::default();
+    let mut episodes = 5;
+    let mut seed = 1;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            println!("{}", USAGE);
+            return 0;
+        }
+        let value = match args.next() {
+            Some(value) => value,
+            None => {
+                println!("{}", USAGE);
+                return 2;
+            }
+        };
+        let is_parsed = match arg.as_str() {
+            "--role" => match value.as_str() {
+                "player" => {
+                    settings.role = AgentRole::Player;
+                    true
+                }
+                "enemy" => {
+                    settings.role = AgentRole::Enemy;
+                    true
+                }
+                _ => false,
+            },
+            "--opponent" => OpponentAi::from_name(value)
+                .map(|opponent| settings.opponent = opponent)
+                .is_some(),
+            "--episodes" => value.parse().map(|v| episodes = v).is_ok(),
+            "--seed" => value.parse().map(|v| seed = v).is_ok(),
+            "--rewards" => match load_reward_shaping(value) {
+                Ok(rewards) => {
+                    settings.rewards = rewards;
+                    true
+                }
+                Err(e) => {
+                    println!("{}: {}", value, e);
+                    return 1;
+                }
+            },
+            _ => false,
+        };
+        if !is_parsed {
+            println!("invalid argument {} {}\n{}", arg, value, USAGE);
+            return 2;
+        }
+    }
+
+    let mut env = Environment::new(settings);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut total_steps = 0;
+    let started = Instant::now();
+    for episode in 0..episodes {
+        env.reset(seed.wrapping_add(episode));
+        let mut episode_return = 0.0;
+        let mut steps = 0;
+        loop {
+            let values: Vec<f32> = (0..Action::SIZE)
+                .map(|_| rng.gen_range(-1.0..1.0))
+                .collect();
+            let action = Action::from_vector(&values);
+            let (_, reward, done) = env.step(action);
+            episode_return += reward;
+            steps += 1;
+            if done {
+                break;
+            }
+        }
+        total_steps += steps;
+        println!(
+            "episode {}: return {:.2} over {} steps ({:.1}s of play)",
+            episode,
+            episode_return,
+            steps,
+            env.sim.time / 1000.0
+        );
+    }
+    let elapsed = started.elapsed().as_secs_f32().max(f32::EPSILON);
+    println!(
+        "{} steps in {:.2}s, {:.0} steps per second",
+        total_steps,
+        elapsed,
+        total_steps as f32 / elapsed
+    );
+    0
+}
@@ -0,0 +1,390 @@
+use crate::behaviors::enemy_ai::data_tree_enemy_ai::DataTreeEnemyAI;
+use crate::behaviors::model::Behavior;
+use crate::behaviors::tree_definition::{get_builtin_trees, TreeDefinition, TreeNodeDefinition};
+use crate::simulation::{ScriptedPlayer, SimSettings, Simulation};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::path::PathBuf;
+
+const USAGE: &str = "usage: evolve [--seed <n>] [--generations <n>] [--population <n>] [--fights <n>] [--skill <0..1>] [--keep <n>] [--out <dir>]";
+const ALL_BEHAVIORS: [Behavior; 7] = [
+    Behavior::Idle,
+    Behavior::MoveToPlayer,
+    Behavior::MoveToRandom,
+    Behavior::AttackPlayer,
+    Behavior::AttackRandom,
+    Behavior::RunAway,
+    Behavior::Dodge,
+];
+// the built-in trees are three levels of children deep
+const MAX_DEPTH: usize = 4;
+// fraction of the player's hp lost in the fairest fights
+const FAIR_DAMAGE_RATIO: f32 = 0.5;
+// losing more than this fraction of hp within a second is a cheap shot
+const FAIR_BURST_RATIO: f32 = 0.25;
+
+pub struct EvolutionSettings {
+    pub seed: u64,
+    pub generations: usize,
+    pub population: usize,
+    // fights per candidate per generation, every candidate gets the same ones
+    pub fights: usize,
+    pub player_skill: f32,
+    pub keep: usize,
+    pub out_dir: PathBuf,
+    pub mutation_rate: f32,
+    pub tournament_size: usize,
+    pub damage_weight: f32,
+    pub survival_weight: f32,
+    pub fairness_weight: f32,
+    pub sim: SimSettings,
+}
+
+impl Default for EvolutionSettings {
+    fn default() -> Self {
+        EvolutionSettings {
+            seed: 1,
+            generations: 20,
+            population: 24,
+            fights: 3,
+            player_skill: 0.6,
+            keep: 3,
+            out_dir: PathBuf::from("evolved_trees"),
+            mutation_rate: 0.3,
+            tournament_size: 3,
+            damage_weight: 1.0,
+            survival_weight: 1.0,
+            fairness_weight: 1.0,
+            sim: SimSettings::default(),
+        }
+    }
+}
+
+/// A behavior tree in the shape the genetic operators work on
+#[derive(Debug, Clone)]
+struct Gene {
+    behavior: Behavior,
+    children: Option<Box<(Gene, Gene)>>,
+}
+
+impl Gene {
+    fn random(rng: &mut StdRng, depth: usize) -> Gene {
+        let has_children = depth < MAX_DEPTH && rng.gen_bool(0.6);
+        Gene {
+            behavior: ALL_BEHAVIORS.choose(rng).unwrap().clone(),
+            children: match has_children {
+                true => Some(Box::new((
+                    Gene::random(rng, depth + 1),
+                    Gene::random(rng, depth + 1),
+                ))),
+                false => None,
+            },
+        }
+    }
+
+    fn from_definition(tree: &TreeDefinition, id: u32) -> Gene {
+        let node = tree
+            .nodes
+            .iter()
+            .find(|node| node.id == id)
+            .expect("trees are validated before evolving");
+        Gene {
+            behavior: node.behavior.clone(),
+            children: node.children.map(|(left, right)| {
+                Box::new((
+                    Gene::from_definition(tree, left),
+                    Gene::from_definition(tree, right),
+                ))
+            }),
+        }
+    }
+
+    fn to_definition(&self, name: &str) -> TreeDefinition {
+        let mut nodes = vec![];
+        self.push_nodes(None, &mut nodes);
+        TreeDefinition {
+            name: name.to_string(),
+            nodes,
+        }
+    }
+
+    fn push_nodes(&self, parent: Option<u32>, nodes: &mut Vec<TreeNodeDefinition>) -> u32 {
+        let id = nodes.len() as u32;
+        nodes.push(TreeNodeDefinition {
+            id,
+            behavior: self.behavior.clone(),
+            parent,
+            children: None,
+        });
+        if let Some(children) = &self.children {
+            let left = children.0.push_nodes(Some(id), nodes);
+            let right = children.1.push_nodes(Some(id), nodes);
+            nodes[id as usize].children = Some((left, right));
+        }
+        id
+    }
+
+    fn count(&self) -> usize {
+        1 + self
+            .children
+            .as_ref()
+            .map_or(0, |children| children.0.count() + children.1.count())
+    }
+
+    /// The `index`th node in pre-order, along with its depth
+    fn get_mut(&mut self, index: usize, depth: usize) -> Option<(&mut Gene, usize)> {
+        if index == 0 {
+            return Some((self, depth));
+        }
+        let children = self.children.as_mut()?;
+        let left_count = children.0.count();
+        match index - 1 < left_count {
+            true => children.0.get_mut(index - 1, depth + 1),
+            false => children.1.get_mut(index - 1 - left_count, depth + 1),
+        }
+    }
+
+    fn prune(&mut self, depth: usize) {
+        if depth >= MAX_DEPTH {
+            self.children = None;
+        }
+        if let Some(children) = &mut self.children {
+            children.0.prune(depth + 1);
+            children.1.prune(depth + 1);
+        }
+    }
+
+    fn mutate(&mut self, rng: &mut StdRng) {
+        let index = rng.gen_range(0..self.count());
+        let (node, depth) = self.get_mut(index, 0).unwrap();
+        match rng.gen_range(0..3) {
+            // grow a leaf, or cut a branch back to a leaf
+            0 if node.children.is_none() && depth < MAX_DEPTH => {
+                node.children = Some(Box::new((
+                    Gene::random(rng, depth + 1),
+                    Gene::random(rng, depth + 1),
+                )))
+            }
+            1 if node.children.is_some() => node.children = None,
+            _ => node.behavior = ALL_BEHAVIORS.choose(rng).unwrap().clone(),
+        }
+    }
+
+    /// A copy of `self` with a random subtree swapped for a random subtree of `other`
+    fn crossover(&self, other: &Gene, rng: &mut StdRng) -> Gene {
+        let mut child = self.clone();
+        let mut donor = other.clone();
+        let donor_index = rng.gen_range(0..donor.count());
+        let subtree = donor.get_mut(donor_index, 0).unwrap().0.clone();
+        let index = rng.gen_range(0..child.count());
+        *child.get_mut(index, 0).unwrap().0 = subtree;
+        child.prune(0);
+        child
+    }
+}
+
+/// How a tree did over its fights, every score is in 0..1
+#[derive(Debug, Clone, Copy, Default)]
+struct Fitness {
+    damage: f32,
+    survival: f32,
+    fairness: f32,
+    total: f32,
+}
+
+fn run_fight(tree: &TreeDefinition, settings: &EvolutionSettings, seed: u64) -> Fitness {
+    let mut sim = Simulation::new(settings.sim.clone(), seed);
+    let mut player = ScriptedPlayer::new(settings.player_skill, seed.wrapping_add(1));
+    for i in 0..settings.sim.enemy_count {
+        let ai = DataTreeEnemyAI::from_definition(tree.clone(), seed.wrapping_add(2 + i as u64));
+        sim.spawn_enemy(Box::new(ai));
+    }
+    while !sim.is_done() {
+        let input = player.get_input(&sim);
+        sim.step(input);
+    }
+    sim.finish();
+
+    let stats = &sim.stats;
+    let player_hp = settings.sim.player_hp;
+    let damage = (stats.damage_to_player / player_hp).min(1.0);
+    let survival = match stats.enemy_lifetimes.is_empty() {
+        true => 0.0,
+        false => {
+            stats.enemy_lifetimes.iter().sum::<f32>()
+                / stats.enemy_lifetimes.len() as f32
+                / settings.sim.max_time
+        }
+    };
+    // worst damage taken inside any one second
+    let burst = stats
+        .player_hits
+        .iter()
+        .map(|(start, _)| {
+            stats
+                .player_hits
+                .iter()
+                .filter(|(time, _)| *time >= *start && *time < start + 1000.0)
+                .map(|(_, amount)| amount)
+                .sum::<f32>()
+        })
+        .fold(0.0, f32::max)
+        / player_hp;
+    let fairness = (1.0 - (damage - FAIR_DAMAGE_RATIO).abs() / FAIR_DAMAGE_RATIO).max(0.0)
+        * (1.0 - (burst / FAIR_BURST_RATIO).min(1.0) * 0.5);
+    let total = settings.damage_weight * damage
+        + settings.survival_weight * survival
+        + settings.fairness_weight * fairness;
+    Fitness {
+        damage,
+        survival,
+        fairness,
+        total,
+    }
+}
+
+fn evaluate(tree: &TreeDefinition, settings: &EvolutionSettings, fight_seeds: &[u64]) -> Fitness {
+    let mut fitness = Fitness::default();
+    for seed in fight_seeds {
+        let fight = run_fight(tree, settings, *seed);
+        fitness.damage += fight.damage;
+        fitness.survival += fight.survival;
+        fitness.fairness += fight.fairness;
+        fitness.total += fight.total;
+    }
+    let fights = fight_seeds.len().max(1) as f32;
+    Fitness {
+        damage: fitness.damage / fights,
+        survival: fitness.survival / fights,
+        fairness: fitness.fairness / fights,
+        total: fitness.total / fights,
+    }
+}
+
+fn select<'a>(ranked: &'a [(Gene, Fitness)], rng: &mut StdRng, size: usize) -> &'a Gene {
+    // ranked is sorted best first, so the lowest index drawn wins the tournament
+    let winner = (0..size.max(1))
+        .map(|_| rng.gen_range(0..ranked.len()))
+        .min()
+        .unwrap();
+    &ranked[winner].0
+}
+
+/// Evolves behavior trees against the scripted player and writes the best ones to
+/// `settings.out_dir`. The same seed always gives the same trees.
+pub fn evolve(settings: &EvolutionSettings) -> std::io::Result<()> {
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+    let mut population: Vec<Gene> = get_builtin_trees()
+        .iter()
+        .map(|tree| Gene::from_definition(tree, tree.nodes[0].id))
+        .collect();
+    while population.len() < settings.population.max(2) {
+        population.push(Gene::random(&mut rng, 0));
+    }
+
+    let mut ranked = vec![];
+    for generation in 0..settings.generations.max(1) {
+        let fight_seeds: Vec<u64> = (0..settings.fights.max(1)).map(|_| rng.gen()).collect();
+        ranked = population
+            .iter()
+            .map(|gene| {
+                let fitness = evaluate(&gene.to_definition("candidate"), settings, &fight_seeds);
+                (gene.clone(), fitness)
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_by(|a, b| b.1.total.total_cmp(&a.1.total));
+        let best = ranked[0].1;
+        println!(
+            "generation {}: best {:.3} (damage {:.2}, survival {:.2}, fairness {:.2}, {} nodes)",
+            generation,
+            best.total,
+            best.damage,
+            best.survival,
+            best.fairness,
+            ranked[0].0.count()
+        );
+
+        // the best two carry over untouched
+        population = ranked
+            .iter()
+            .take(2)
+            .map(|(gene, _)| gene.clone())
+            .collect();
+        while population.len() < ranked.len() {
+            let parent = select(&ranked, &mut rng, settings.tournament_size);
+            let other = select(&ranked, &mut rng, settings.tournament_size);
+            let mut child = parent.crossover(other, &mut rng);
+            if rng.gen::<f32>() < settings.mutation_rate {
+                child.mutate(&mut rng);
+            }
+            population.push(child);
+        }
+    }
+
+    std::fs::create_dir_all(&settings.out_dir)?;
+    for (rank, (gene, fitness)) in ranked.iter().take(settings.keep).enumerate() {
+        let name = format!("evolved_{}", rank + 1);
+        let tree = gene.to_definition(&name);
+        debug_assert!(tree.validate().is_empty());
+        let source = toml::to_string(&tree).map_err(std::io::Error::other)?;
+        let path = settings.out_dir.join(format!("{}.toml", name));
+        std::fs::write(
+            &path,
+            format!(
+                "# seed {}, fitness {:.3} (damage {:.2}, survival {:.2}, fairness {:.2})\n{}",
+                settings.seed,
+                fitness.total,
+                fitness.damage,
+                fitness.survival,
+                fitness.fairness,
+                source
+            ),
+        )?;
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+/// Parses the `evolve` command line and runs it, returning the process exit code
+pub fn run(args: &[String]) -> i32 {
+    let mut settings = EvolutionSettings::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            println!("{}", USAGE);
+            return 0;
+        }
+        let value = match args.next() {
+            Some(value) => value,
+            None => {
+                println!("{}", USAGE);
+                return 2;
+            }
+        };
+        let is_parsed = match arg.as_str() {
+            "--seed" => value.parse().map(|v| settings.seed = v).is_ok(),
+            "--generations" => value.parse().map(|v| settings.generations = v).is_ok(),
+            "--population" => value.parse().map(|v| settings.population = v).is_ok(),
+            "--fights" => value.parse().map(|v| settings.fights = v).is_ok(),
+            "--skill" => value.parse().map(|v| settings.player_skill = v).is_ok(),
+            "--keep" => value.parse().map(|v| settings.keep = v).is_ok(),
+            "--out" => {
+                settings.out_dir = PathBuf::from(value);
+                true
+            }
+            _ => false,
+        };
+        if !is_parsed {
+            println!("invalid argument {} {}\n{}", arg, value, USAGE);
+            return 2;
+        }
+    }
+    match evolve(&settings) {
+        Ok(_) => 0,
+        Err(e) => {
+            println!("Error writing evolved trees: {:?}", e);
+            1
+        }
+    }
+}
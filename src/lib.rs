@@ -0,0 +1,25 @@
+// The AI, the headless simulation and the offline tools build without ggez, everything that
+// needs a window or audio sits behind the `game` feature
+pub mod behaviors;
+pub mod entity;
+pub mod environment;
+pub mod evolution;
+pub mod simulation;
+pub mod tree_tool;
+
+#[cfg(feature = "game")]
+pub mod actors;
+#[cfg(feature = "game")]
+pub mod adaptation;
+#[cfg(feature = "game")]
+pub mod asset_manager;
+#[cfg(feature = "game")]
+pub mod autopilot;
+#[cfg(feature = "game")]
+pub mod bot_server;
+#[cfg(feature = "game")]
+pub mod difficulty;
+#[cfg(feature = "game")]
+pub mod director;
+#[cfg(feature = "game")]
+pub mod main_game_state;
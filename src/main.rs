@@ -1,13 +1,9 @@
-mod actors;
-mod asset_manager;
-pub mod behaviors;
-mod main_game_state;
 mod main_menu_game_state;
 
-use crate::asset_manager::Assets;
-use crate::behaviors::enemy_ai::model::EnemyAi;
-use crate::behaviors::model::BehaviorTreeTrait;
-use crate::main_game_state::build_main_game_state;
+use gg_ez_test_02::asset_manager::Assets;
+use gg_ez_test_02::autopilot::AutopilotSkill;
+use gg_ez_test_02::bot_server::{BotMode, BotServer, DEFAULT_BOT_PORT};
+use gg_ez_test_02::main_game_state::build_main_game_state;
 use ggez::audio::SoundSource;
 use ggez::conf::{NumSamples, WindowMode, WindowSetup};
 use ggez::graphics::Drawable;
@@ -121,6 +117,56 @@ impl MetaState {
 }
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    // `autopilot [novice|average|expert]` lets a bot play, for soak tests and attract mode
+    let autopilot_skill = match args.first().map(String::as_str) {
+        Some("autopilot") => {
+            let name = args.get(1).map(String::as_str).unwrap_or("average");
+            match AutopilotSkill::from_name(name) {
+                Some(skill) => Some(skill),
+                None => {
+                    println!("usage: autopilot [novice|average|expert]");
+                    std::process::exit(2);
+                }
+            }
+        }
+        _ => None,
+    };
+    // `serve [--port <n>] [--lockstep]` lets a bot play over a localhost socket
+    let bot_server = match args.first().map(String::as_str) {
+        Some("serve") => {
+            let mut port = DEFAULT_BOT_PORT;
+            let mut mode = BotMode::Realtime;
+            let mut serve_args = args[1..].iter();
+            while let Some(arg) = serve_args.next() {
+                let is_parsed = match arg.as_str() {
+                    "--lockstep" => {
+                        mode = BotMode::Lockstep;
+                        true
+                    }
+                    "--port" => serve_args
+                        .next()
+                        .and_then(|value| value.parse().ok())
+                        .map(|value| port = value)
+                        .is_some(),
+                    _ => false,
+                };
+                if !is_parsed {
+                    println!("usage: serve [--port <n>] [--lockstep]");
+                    std::process::exit(2);
+                }
+            }
+            match BotServer::bind(port, mode) {
+                Ok(server) => Some(server),
+                Err(e) => {
+                    println!("Error starting bot server: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => None,
+    };
+
     let window_mode = WindowMode::default().dimensions(1920.0, 1080.0);
     let window_setup = WindowSetup::default().title("Hello ggez");
     let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
@@ -152,6 +198,6 @@ fn main() {
     meta_state.set_cursor_position(&mut ctx);
     meta_state.set_cursor_icon(&mut ctx);
     let assets = meta_state.set_assets(&mut ctx);
-    let (ctx, game_state) = build_main_game_state(ctx, assets);
+    let (ctx, game_state) = build_main_game_state(ctx, assets, autopilot_skill, bot_server);
     event::run(ctx, event_loop, game_state);
 }
@@ -1,46 +1,155 @@
-use crate::actors::enemy::{create_boss_enemy, create_enemy};
+use crate::actors::boss::{get_boss_movement_target, BossState};
+use crate::actors::bullet_pattern::{handle_projectile_modifier, PatternEmitter};
+use crate::actors::enemy::{
+    create_boss_enemy, create_carrier_enemy, create_enemy, create_fighter_enemy,
+    create_kamikaze_enemy, create_mine_layer_enemy, create_splitter_enemy,
+    get_carrier_spawn_chance, get_kamikaze_spawn_chance, get_mine_layer_spawn_chance,
+    get_split_directions, get_splitter_spawn_chance, Blast, Carrier, EnemyKind, Splitter,
+    BLAST_DURATION, CARRIER_LAUNCH_INTERVAL, CARRIER_MAX_FIGHTERS, FIGHTER_ORPHAN_DURATION,
+    KAMIKAZE_BLAST_DAMAGE, KAMIKAZE_BLAST_RADIUS, MINE_LAYER_DROP_INTERVAL,
+};
+use crate::actors::hazard::{
+    create_hazard, is_in_range, is_mine_armed, roll_hazard_kind, HazardKind, CLOUD_DAMAGE,
+    MINE_BLAST_DAMAGE, MINE_BLAST_RADIUS, MINE_HIT_RADIUS, MINE_TRIGGER_RADIUS,
+    SLOW_FIELD_MAX_SPEED,
+};
 use crate::actors::models::{
     create_boss_enemy_projectile_mesh, create_boss_enemy_spaceship_mesh,
-    create_enemy_projectile_mesh, create_enemy_spaceship_mesh, create_player_alt_projectile_mesh,
-    create_player_projectile_mesh, create_spaceship_mesh, take_damage, Actor, ActorType,
+    create_carrier_spaceship_mesh, create_enemy_projectile_mesh, create_enemy_spaceship_mesh,
+    create_fighter_spaceship_mesh, create_hazard_mesh, create_kamikaze_spaceship_mesh,
+    create_mine_layer_spaceship_mesh, create_pickup_mesh, create_player_alt_projectile_mesh,
+    create_player_projectile_mesh, create_spaceship_mesh, create_splitter_spaceship_mesh,
+    take_damage, Actor, ActorType, EntityId,
+};
+use crate::actors::pickup::{
+    create_pickup, roll_pickup_drop, PickupType, DEFAULT_PICKUP_DROP_CHANCE,
+};
+use crate::actors::player::{
+    absorb_damage, create_player, find_safe_spawn_point, handle_player_status,
+    leave_dash_afterimage, PlayerStatus, DASH_COOLDOWN, DASH_TRAIL_LIFETIME, DASH_VELOCITY,
 };
-use crate::actors::player::create_player;
 use crate::actors::projectile::{
     create_boss_enemy_projectile, create_enemy_projectile, create_player_alt_projectile,
-    create_player_projectile, handle_timed_life,
+    create_player_projectile, handle_timed_life, ENEMY_PROJECTILE_SPEED, PLAYER_PROJECTILE_SPEED,
 };
+use crate::actors::ram::{RamAttack, RamPhase};
+use crate::actors::shield::{get_shield_chance, Shield, ShieldHit};
+use crate::adaptation::PlayerModel;
 use crate::asset_manager::Assets;
+use crate::autopilot::{Autopilot, AutopilotSkill, AutopilotView, BotInput, TargetView};
 use crate::behaviors::enemy_ai::aggressive_enemy_ai::AggressiveEnemyAI;
+use crate::behaviors::enemy_ai::debugger::{dump_traces, TraceLog};
 use crate::behaviors::enemy_ai::elusive_enemy_ai::ElusiveEnemyAI;
-use crate::behaviors::enemy_ai::model::EnemyAi;
+use crate::behaviors::enemy_ai::fighter_enemy_ai::FighterEnemyAI;
+use crate::behaviors::enemy_ai::hfsm_enemy_ai::{HfsmDefinition, HfsmEnemyAI};
+use crate::behaviors::enemy_ai::kamikaze_enemy_ai::KamikazeEnemyAI;
+use crate::behaviors::enemy_ai::model::{create_enemy_ai, EnemyAi};
 use crate::behaviors::enemy_ai::normal_enemy_ai::NormalEnemyAI;
-use crate::behaviors::model::BehaviorTreeTrait;
+use crate::behaviors::enemy_ai::perception::{Awareness, Perception};
+use crate::behaviors::enemy_ai::utility_enemy_ai::UtilityEnemyAI;
+use crate::behaviors::enemy_ai::world_view::{
+    ActorView, AiCommand, Faction, HazardView, ProjectileView, SoundCue, WaveInfo, WeaponId,
+    WorldView, NEARBY_ALLY_RADIUS,
+};
+use crate::behaviors::model::{BehaviorTreeTrait, BehaviorTreeType, NodeTrait};
+use crate::behaviors::squad::{get_max_attack_tokens, SquadCoordinator, FLANK_DISTANCE};
+use crate::behaviors::steering::{
+    apply_steering_force, get_steering_force, SteeringAgent, SteeringGoal, SteeringWeights,
+};
+use crate::bot_server::{
+    BotMode, BotServer, EnemySnapshot, PlayerSnapshot, ProjectileSnapshot, Snapshot, WaveSnapshot,
+};
+use crate::difficulty::Difficulty;
+use crate::director::Director;
 use ggez::audio::SoundSource;
 use ggez::event::MouseButton;
-use ggez::graphics::{Canvas, Color, Drawable, Text};
-use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::graphics::{Canvas, Color, DrawMode, DrawParam, Drawable, Mesh, Rect, Text};
+use ggez::input::keyboard::{KeyCode, KeyInput, KeyMods};
 use ggez::mint::Point2;
 use ggez::{event, Context, GameError, GameResult};
 use rand::prelude::*;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::time::Duration;
 
+// where F5 writes the behavior trace of every enemy
+const AI_TRACE_PATH: &str = "ai_trace.log";
+// fixed time step while an external bot plays in lockstep
+const LOCKSTEP_DT: Duration = Duration::from_millis(16);
+// keys the dash can't be rebound to because something else already uses them
+const RESERVED_KEYS: [KeyCode; 15] = [
+    KeyCode::W,
+    KeyCode::A,
+    KeyCode::S,
+    KeyCode::D,
+    KeyCode::Return,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Escape,
+    KeyCode::Tab,
+    KeyCode::F1,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+];
+
 pub enum PlayState {
     MainMenu = 1,
     MainGame = 2,
 }
 
+/// A shot an enemy has been cleared to fire once its cooldown is up
+struct AttackOrder {
+    weapon: WeaponId,
+    target: (f32, f32),
+}
+
 pub struct GameState {
     dt: Duration,
+    // time the game has simulated, advanced by `dt` so AI timing stays in step with lockstep
+    game_time: Duration,
     assets: Assets,
     player: Actor,
+    player_status: PlayerStatus,
+    difficulty: Difficulty,
     enemy: Vec<Actor>,
+    boss_state: Option<BossState>,
     projectiles: Vec<Actor>,
+    pickups: Vec<Actor>,
+    // remaining duration in ms of each timed pickup effect
+    pickup_effects: HashMap<PickupType, f32>,
     keys_pressed: HashSet<KeyCode>,
+    dash_key: KeyCode,
+    is_rebinding_dash: bool,
+    is_ai_debug_visible: bool,
+    // enemy whose whole behavior tree the debug overlay shows
+    selected_enemy: Option<EntityId>,
+    autopilot: Option<Autopilot>,
+    // skill F6 hands control to
+    autopilot_skill: AutopilotSkill,
+    bot_server: Option<BotServer>,
     kills: u64,
+    score: u64,
     alt_cd: f32,
     game_state_data: std::collections::HashMap<String, f32>,
-    attacking_enemies: Vec<usize>,
+    attacking_enemies: HashMap<EntityId, AttackOrder>,
+    rams: HashMap<EntityId, RamAttack>,
+    blasts: Vec<Blast>,
+    carriers: HashMap<EntityId, Carrier>,
+    // fighters whose carrier died, with the ms left before they self-destruct
+    orphaned_fighters: HashMap<EntityId, f32>,
+    splitters: HashMap<EntityId, Splitter>,
+    shields: HashMap<EntityId, Shield>,
+    // ms until each mine-layer drops its next hazard
+    mine_layers: HashMap<EntityId, f32>,
+    hazards: Vec<Actor>,
+    // enemies without one, like bosses, know where the player is at all times
+    perceptions: HashMap<EntityId, Perception>,
+    squad_coordinator: SquadCoordinator,
+    director: Director,
+    // the player's habits this run, for the adaptive enemies
+    player_model: PlayerModel,
     play_state: PlayState,
 }
 fn handle_player_movement(
@@ -67,22 +176,7 @@ fn handle_player_movement(
         player.target_x += player.velocity * dt.as_secs_f32();
         is_moving = true;
     }
-    // Check if the player is outside the screen boundaries
-    if player.x <= 0.0 {
-        player.x = 0.0;
-        player.target_x = player.x + 10.0;
-    } else if player.x + player.image.clone().unwrap().width() as f32 >= screen_width {
-        player.x = screen_width - player.image.clone().unwrap().width() as f32;
-        player.target_x = player.x - 10.0;
-    }
-
-    if player.y <= 0.0 {
-        player.y = 0.0;
-        player.target_y = player.y + 10.0;
-    } else if player.y + player.image.clone().unwrap().height() as f32 >= screen_height {
-        player.y = screen_height - player.image.clone().unwrap().height() as f32;
-        player.target_y = player.y - 10.0;
-    }
+    clamp_player_to_screen(player, screen_width, screen_height);
     // If no movement keys are pressed, reduce the actor's velocity to simulate deceleration
     if !is_moving && player.velocity > 0.0 {
         player.velocity -= 1.0 * dt.as_millis() as f32; // Deceleration factor of 10.0
@@ -118,13 +212,48 @@ fn handle_player_movement(
     player.y += movement.1;
 }
 
-fn handle_enemy_movement(enemy: &mut Actor, dt: Duration) {
-    // Calculate the direction vector from the actor's current position to the target position
-    let direction = ((enemy.target_x - enemy.x), (enemy.target_y - enemy.y));
+fn clamp_player_to_screen(player: &mut Actor, screen_width: f32, screen_height: f32) {
+    // Check if the player is outside the screen boundaries
+    if player.x <= 0.0 {
+        player.x = 0.0;
+        player.target_x = player.x + 10.0;
+    } else if player.x + player.image.clone().unwrap().width() as f32 >= screen_width {
+        player.x = screen_width - player.image.clone().unwrap().width() as f32;
+        player.target_x = player.x - 10.0;
+    }
 
-    // Calculate the length of the direction vector
-    let length = (direction.0.powi(2) + direction.1.powi(2)).sqrt();
+    if player.y <= 0.0 {
+        player.y = 0.0;
+        player.target_y = player.y + 10.0;
+    } else if player.y + player.image.clone().unwrap().height() as f32 >= screen_height {
+        player.y = screen_height - player.image.clone().unwrap().height() as f32;
+        player.target_y = player.y - 10.0;
+    }
+}
+
+fn handle_player_dash(
+    player: &mut Actor,
+    status: &mut PlayerStatus,
+    dt: Duration,
+    screen_width: f32,
+    screen_height: f32,
+) {
+    leave_dash_afterimage(status, player.x, player.y);
+    player.x += status.dash_direction.0 * DASH_VELOCITY * dt.as_secs_f32();
+    player.y += status.dash_direction.1 * DASH_VELOCITY * dt.as_secs_f32();
+    // Keep the target on the player so normal movement picks up from where the dash ended
+    player.target_x = player.x;
+    player.target_y = player.y;
+    status.dash_remaining -= dt.as_secs_f32() * 1000.0;
+    clamp_player_to_screen(player, screen_width, screen_height);
+}
 
+fn handle_enemy_movement(
+    enemy: &mut Actor,
+    dt: Duration,
+    neighbors: &[SteeringAgent],
+    player: &SteeringAgent,
+) {
     enemy.attack_cooldown = match enemy.attack_cooldown {
         Some(mut cd) => {
             if cd <= 0.0 {
@@ -137,21 +266,21 @@ fn handle_enemy_movement(enemy: &mut Actor, dt: Duration) {
         None => None,
     };
 
-    // Normalize the direction vector to get a unit direction vector
-    let unit_direction = if length > 0.0 {
-        (direction.0 / length, direction.1 / length)
-    } else {
-        (0.0, 0.0)
+    let agent = SteeringAgent {
+        position: (enemy.x, enemy.y),
+        velocity: enemy.current_velocity,
+        max_speed: enemy.velocity,
     };
-    // Update the actor's velocity
-    // Calculate the movement vector by multiplying the unit direction vector by the actor's velocity and the elapsed time
-    let movement = (
-        unit_direction.0 * enemy.velocity * dt.as_secs_f32(),
-        unit_direction.1 * enemy.velocity * dt.as_secs_f32(),
-    );
-    // Update the actor's position
-    enemy.x += movement.0;
-    enemy.y += movement.1;
+    let weights = match &enemy.ai {
+        Some(ai) => ai.get_steering_weights(),
+        None => SteeringWeights::default(),
+    };
+    let goal = enemy.steering_goal.unwrap_or(SteeringGoal::Hold);
+    // Blend the goal with the flocking forces and turn it into a new velocity
+    let force = get_steering_force(&agent, goal, &weights, neighbors, player);
+    enemy.current_velocity = apply_steering_force(&agent, force, &weights, dt.as_secs_f32());
+    enemy.x += enemy.current_velocity.0 * dt.as_secs_f32();
+    enemy.y += enemy.current_velocity.1 * dt.as_secs_f32();
 }
 
 fn handle_projectile_trajectory(projectile: &mut Actor, dt: Duration) {
@@ -195,6 +324,7 @@ impl GameState {
         game_state_data.insert("background_tile_2_y_pos".to_string(), 0.0);
         game_state_data.insert("wave_count".to_string(), 1.0);
         game_state_data.insert("boss_count".to_string(), 1.0);
+        game_state_data.insert("pickup_drop_chance".to_string(), DEFAULT_PICKUP_DROP_CHANCE);
         let enemy = create_enemy(
             1000.0,
             500.0,
@@ -205,17 +335,48 @@ impl GameState {
             None,
             None,
         );
+        let director = Director::new(assets.director_settings.clone());
+        let player_model = PlayerModel::new(
+            assets.adaptation_settings.clone(),
+            Difficulty::Normal.max_adaptation(),
+        );
         GameState {
             dt: Duration::from_secs(0),
+            game_time: Duration::from_secs(0),
             assets,
             player,
+            player_status: PlayerStatus::new(&Difficulty::Normal.player_settings()),
+            difficulty: Difficulty::Normal,
             enemy: vec![enemy],
+            boss_state: None,
             projectiles: vec![],
+            pickups: vec![],
+            pickup_effects: HashMap::new(),
             keys_pressed: HashSet::new(),
+            dash_key: KeyCode::LShift,
+            is_rebinding_dash: false,
+            is_ai_debug_visible: false,
+            selected_enemy: None,
+            autopilot: None,
+            autopilot_skill: AutopilotSkill::Average,
+            bot_server: None,
             kills: 0,
+            score: 0,
             alt_cd: 0.0,
             game_state_data,
-            attacking_enemies: vec![],
+            attacking_enemies: HashMap::new(),
+            rams: HashMap::new(),
+            blasts: vec![],
+            carriers: HashMap::new(),
+            orphaned_fighters: HashMap::new(),
+            splitters: HashMap::new(),
+            shields: HashMap::new(),
+            mine_layers: HashMap::new(),
+            hazards: vec![],
+            perceptions: HashMap::new(),
+            squad_coordinator: SquadCoordinator::new(get_max_attack_tokens(1.0)),
+            director,
+            player_model,
             play_state: PlayState::MainMenu,
         }
     }
@@ -236,6 +397,243 @@ impl GameState {
         }
     }
 
+    fn upgrade_weapon_level(&mut self) {
+        let level = self.game_state_data.get("weapon_level");
+        let level = match level {
+            Some(level) => {
+                if level >= &5.0 {
+                    5.0
+                } else if level >= &3.0 {
+                    level + 0.25
+                } else {
+                    level + 1.0
+                }
+            }
+            None => 2.0,
+        };
+        self.game_state_data
+            .insert("weapon_level".to_string(), level);
+    }
+
+    /// Dashes along the current WASD direction, or toward the cursor when standing still
+    fn start_dash(&mut self, ctx: &mut Context) {
+        let mut direction = (0.0, 0.0);
+        if self.keys_pressed.contains(&KeyCode::W) {
+            direction.1 -= 1.0;
+        }
+        if self.keys_pressed.contains(&KeyCode::S) {
+            direction.1 += 1.0;
+        }
+        if self.keys_pressed.contains(&KeyCode::A) {
+            direction.0 -= 1.0;
+        }
+        if self.keys_pressed.contains(&KeyCode::D) {
+            direction.0 += 1.0;
+        }
+        if direction == (0.0, 0.0) {
+            let mouse_position = ctx.mouse.position();
+            direction = (
+                mouse_position.x - self.player.x,
+                mouse_position.y - self.player.y,
+            );
+        }
+        if self.player_status.start_dash(direction) {
+            self.assets.player_laser_1.set_volume(0.2);
+            let res = self.assets.player_laser_1.play(ctx);
+            match res {
+                Ok(_) => (),
+                Err(e) => println!("Error playing dash sound: {:?}", e),
+            }
+        }
+    }
+
+    fn respawn_player(&mut self, screen_width: f32, screen_height: f32) {
+        let player_settings = self.difficulty.player_settings();
+        self.player_status.shield = player_settings.max_shield;
+        self.player_status.shield_recharge_cd = 0.0;
+        self.player_status.invulnerable_cd = player_settings.respawn_invulnerability;
+
+        let threats: Vec<(f32, f32)> = self
+            .enemy
+            .iter()
+            .map(|enemy| (enemy.x, enemy.y))
+            .chain(
+                self.projectiles
+                    .iter()
+                    .filter(|projectile| projectile.actor_type == ActorType::EnemyProjectile)
+                    .map(|projectile| (projectile.x, projectile.y)),
+            )
+            .collect();
+        let (x, y) = find_safe_spawn_point(screen_width, screen_height, &threats);
+        self.player.hp = player_settings.max_hp;
+        self.player.x = x;
+        self.player.y = y;
+        self.player.target_x = x;
+        self.player.target_y = y;
+        self.player.velocity = 0.0;
+
+        // Clear out enemy projectiles around the spawn point
+        for projectile in &mut self.projectiles {
+            if projectile.actor_type != ActorType::EnemyProjectile {
+                continue;
+            }
+            let distance = ((projectile.x - x).powi(2) + (projectile.y - y).powi(2)).sqrt();
+            if distance < player_settings.respawn_clear_radius {
+                projectile.hp = 0.0;
+            }
+        }
+    }
+
+    fn apply_pickup(&mut self, ctx: &mut Context, pickup_type: PickupType) {
+        match pickup_type {
+            PickupType::Health => {
+                let max_hp = self.difficulty.player_settings().max_hp;
+                self.player.hp = (self.player.hp + 25.0).min(max_hp);
+            }
+            PickupType::WeaponUpgrade => self.upgrade_weapon_level(),
+            PickupType::SpecialReset => self.alt_cd = 0.0,
+            PickupType::Shield | PickupType::ScoreMultiplier | PickupType::RapidFire => (),
+        }
+        if let Some(duration) = pickup_type.effect_duration() {
+            self.pickup_effects.insert(pickup_type, duration);
+        }
+        self.assets.special_atk.set_volume(0.2);
+        let res = self.assets.special_atk.play(ctx);
+        match res {
+            Ok(_) => (),
+            Err(e) => println!("Error playing pickup sound: {:?}", e),
+        }
+    }
+
+    fn play_sound_cue(&mut self, ctx: &mut Context, cue: SoundCue) {
+        let sound = match cue {
+            SoundCue::Laser => {
+                self.assets.laser_1.set_volume(0.4);
+                &mut self.assets.laser_1
+            }
+            SoundCue::Charge => {
+                self.assets.special_atk.set_volume(0.3);
+                &mut self.assets.special_atk
+            }
+            SoundCue::Deflect => {
+                self.assets.deflect.set_volume(0.3);
+                self.assets.deflect.set_pitch(1.8);
+                &mut self.assets.deflect
+            }
+        };
+        let res = sound.play(ctx);
+        match res {
+            Ok(_) => (),
+            Err(e) => println!("Error playing {:?} sound: {:?}", cue, e),
+        }
+    }
+
+    fn get_projectile_views(&self) -> Vec<ProjectileView> {
+        self.projectiles
+            .iter()
+            .map(|projectile| {
+                let direction = (
+                    projectile.target_x - projectile.x,
+                    projectile.target_y - projectile.y,
+                );
+                let length = (direction.0.powi(2) + direction.1.powi(2)).sqrt();
+                ProjectileView {
+                    position: (projectile.x, projectile.y),
+                    velocity: match length > 0.0 {
+                        true => (
+                            direction.0 / length * projectile.velocity,
+                            direction.1 / length * projectile.velocity,
+                        ),
+                        false => (0.0, 0.0),
+                    },
+                    faction: match projectile.actor_type {
+                        ActorType::PlayerProjectile | ActorType::PlayerAltProjectile => {
+                            Faction::Player
+                        }
+                        _ => Faction::Enemy,
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// Hands control to the autopilot, or takes it back
+    fn toggle_autopilot(&mut self, ctx: &mut Context) {
+        match self.autopilot.take() {
+            Some(mut autopilot) => {
+                for input in autopilot.release_keys() {
+                    self.apply_bot_input(ctx, input);
+                }
+            }
+            None => {
+                self.autopilot = Some(Autopilot::new(self.autopilot_skill, rand::random()));
+            }
+        }
+    }
+
+    /// Feeds bot input through the same event handlers a player's input goes through
+    fn apply_bot_input(&mut self, ctx: &mut Context, input: BotInput) {
+        let res = match input {
+            BotInput::KeyDown(key) => event::EventHandler::key_down_event(
+                self,
+                ctx,
+                KeyInput {
+                    scancode: 0,
+                    keycode: Some(key),
+                    mods: KeyMods::empty(),
+                },
+                false,
+            ),
+            BotInput::KeyUp(key) => event::EventHandler::key_up_event(
+                self,
+                ctx,
+                KeyInput {
+                    scancode: 0,
+                    keycode: Some(key),
+                    mods: KeyMods::empty(),
+                },
+            ),
+            BotInput::MouseDown(button, x, y) => {
+                event::EventHandler::mouse_button_down_event(self, ctx, button, x, y)
+            }
+        };
+        match res {
+            Ok(_) => (),
+            Err(e) => println!("Error applying autopilot input {:?}: {:?}", input, e),
+        }
+    }
+
+    fn run_autopilot(&mut self, ctx: &mut Context, screen_width: f32, screen_height: f32) {
+        if self.autopilot.is_none() {
+            return;
+        }
+        let enemies: Vec<TargetView> = self
+            .enemy
+            .iter()
+            .map(|enemy| TargetView {
+                position: (enemy.x, enemy.y),
+                velocity: enemy.current_velocity,
+            })
+            .collect();
+        let projectiles = self.get_projectile_views();
+        let view = AutopilotView {
+            player: (self.player.x, self.player.y),
+            enemies: &enemies,
+            projectiles: &projectiles,
+            player_projectile_speed: PLAYER_PROJECTILE_SPEED,
+            alt_cd: self.alt_cd,
+            dash_key: self.dash_key,
+            arena: (screen_width, screen_height),
+        };
+        let inputs = match &mut self.autopilot {
+            Some(autopilot) => autopilot.get_input(&view, self.dt.as_millis() as f32),
+            None => vec![],
+        };
+        for input in inputs {
+            self.apply_bot_input(ctx, input);
+        }
+    }
+
     pub fn handle_mouse_input(
         &mut self,
         ctx: &mut Context,
@@ -387,10 +785,276 @@ impl GameState {
             }
             _ => (),
         }
+        self.director.record_shots(projectiles.len());
         self.projectiles.extend(projectiles);
     }
+    /// Lays new hazards behind moving mine-layers, then applies and expires the ones on the field.
+    /// Returns the enemies the hazards killed.
+    fn update_hazards(&mut self, ctx: &mut Context) -> HashSet<EntityId> {
+        let is_shielded = self.pickup_effects.contains_key(&PickupType::Shield);
+        for enemy in &self.enemy {
+            let drop_cd = match self.mine_layers.get_mut(&enemy.id) {
+                Some(drop_cd) => drop_cd,
+                None => continue,
+            };
+            *drop_cd -= self.dt.as_secs_f32() * 1000.0;
+            let speed =
+                (enemy.current_velocity.0.powi(2) + enemy.current_velocity.1.powi(2)).sqrt();
+            if *drop_cd > 0.0 || speed <= 0.0 {
+                continue;
+            }
+            *drop_cd = MINE_LAYER_DROP_INTERVAL;
+            // drop it behind the mine-layer so it doesn't sit in its own hazard
+            let hazard_kind = roll_hazard_kind();
+            self.hazards.push(create_hazard(
+                enemy.x - enemy.current_velocity.0 / speed * 30.0,
+                enemy.y - enemy.current_velocity.1 / speed * 30.0,
+                hazard_kind,
+                create_hazard_mesh(ctx, hazard_kind),
+            ));
+        }
+
+        let player_settings = self.difficulty.player_settings();
+        let player_position = (self.player.x, self.player.y);
+        let mut detonations = vec![];
+        let mut killed = HashSet::new();
+        for hazard in &mut self.hazards {
+            handle_timed_life(hazard, self.dt.as_secs_f32());
+            let hazard_kind = match hazard.actor_type {
+                ActorType::Hazard(hazard_kind) => hazard_kind,
+                _ => continue,
+            };
+            match hazard_kind {
+                // Slows the player down in update_game instead
+                HazardKind::SlowField => (),
+                HazardKind::Cloud => {
+                    let damage = CLOUD_DAMAGE * self.dt.as_secs_f32();
+                    if !is_shielded && is_in_range(hazard, player_position, hazard_kind.radius()) {
+                        absorb_damage(
+                            &mut self.player,
+                            &mut self.player_status,
+                            &player_settings,
+                            &damage,
+                        );
+                    }
+                    for enemy in &mut self.enemy {
+                        if enemy.actor_type != ActorType::BossEnemy
+                            && is_in_range(hazard, (enemy.x, enemy.y), hazard_kind.radius())
+                        {
+                            take_damage(enemy, &damage);
+                            if enemy.hp <= 0.0 {
+                                killed.insert(enemy.id);
+                            }
+                        }
+                    }
+                }
+                HazardKind::Mine => {
+                    let is_triggered = is_mine_armed(hazard)
+                        && is_in_range(hazard, player_position, MINE_TRIGGER_RADIUS);
+                    // Shooting a mine sets it off, armed or not
+                    let mut is_shot = false;
+                    for projectile in &mut self.projectiles {
+                        if projectile.actor_type != ActorType::EnemyProjectile
+                            && is_in_range(hazard, (projectile.x, projectile.y), MINE_HIT_RADIUS)
+                        {
+                            projectile.hp = 0.0;
+                            is_shot = true;
+                            break;
+                        }
+                    }
+                    if is_triggered || is_shot {
+                        hazard.hp = 0.0;
+                        detonations.push((hazard.x, hazard.y));
+                    }
+                }
+            }
+        }
+        self.hazards.retain(|hazard| hazard.hp > 0.0);
+
+        for (x, y) in detonations {
+            let distance = ((x - self.player.x).powi(2) + (y - self.player.y).powi(2)).sqrt();
+            if !is_shielded && distance < MINE_BLAST_RADIUS {
+                absorb_damage(
+                    &mut self.player,
+                    &mut self.player_status,
+                    &player_settings,
+                    &MINE_BLAST_DAMAGE,
+                );
+            }
+            for enemy in &mut self.enemy {
+                let distance = ((x - enemy.x).powi(2) + (y - enemy.y).powi(2)).sqrt();
+                if enemy.actor_type != ActorType::BossEnemy && distance < MINE_BLAST_RADIUS {
+                    take_damage(enemy, &MINE_BLAST_DAMAGE);
+                    if enemy.hp <= 0.0 {
+                        killed.insert(enemy.id);
+                    }
+                }
+            }
+            self.assets.damage.set_volume(0.6);
+            let res = self.assets.damage.play(ctx);
+            match res {
+                Ok(_) => (),
+                Err(e) => println!("Error playing mine sound: {:?}", e),
+            }
+            self.blasts.push(Blast {
+                x,
+                y,
+                radius: MINE_BLAST_RADIUS,
+                remaining: BLAST_DURATION,
+            });
+        }
+        killed
+    }
+
+    /// AI and perception profile for a splitter with the given personality
+    fn create_splitter_ai(
+        &self,
+        personality: BehaviorTreeType,
+    ) -> (Box<dyn EnemyAi>, &'static str) {
+        match personality {
+            BehaviorTreeType::AggressiveEnemy => {
+                let ai = AggressiveEnemyAI::from_timing(
+                    self.assets.action_timings.get_profile("aggressive"),
+                );
+                (Box::new(ai), "aggressive")
+            }
+            BehaviorTreeType::ElusiveEnemy => {
+                let ai =
+                    ElusiveEnemyAI::from_timing(self.assets.action_timings.get_profile("elusive"));
+                (Box::new(ai), "elusive")
+            }
+            BehaviorTreeType::NormalEnemy => {
+                let ai =
+                    NormalEnemyAI::from_timing(self.assets.action_timings.get_profile("normal"));
+                (Box::new(ai), "normal")
+            }
+        }
+    }
+
+    /// Counts down every carrier's launch timer and launches a fighter when it runs out
+    fn launch_fighters(&mut self, ctx: &mut Context) {
+        let mut fighters = vec![];
+        for enemy in &self.enemy {
+            let carrier = match self.carriers.get_mut(&enemy.id) {
+                Some(carrier) => carrier,
+                None => continue,
+            };
+            carrier.launch_cd -= self.dt.as_secs_f32() * 1000.0;
+            if carrier.launch_cd > 0.0 || carrier.fighters.len() >= CARRIER_MAX_FIGHTERS {
+                continue;
+            }
+            carrier.launch_cd = CARRIER_LAUNCH_INTERVAL;
+            let fighter = create_fighter_enemy(
+                enemy.x,
+                enemy.y,
+                create_fighter_spaceship_mesh(ctx),
+                Some(self.kills as f32 * 1.10),
+                Some(Box::new(FighterEnemyAI::new())),
+            );
+            carrier.fighters.push(fighter.id);
+            fighters.push(fighter);
+        }
+        for fighter in fighters {
+            let perception =
+                Perception::new(self.assets.perception_profiles.get_profile("aggressive"));
+            self.perceptions.insert(fighter.id, perception);
+            self.enemy.push(fighter);
+        }
+    }
+
+    fn is_in_lockstep(&self) -> bool {
+        self.bot_server
+            .as_ref()
+            .is_some_and(|server| server.mode == BotMode::Lockstep && server.is_connected())
+    }
+
+    fn get_snapshot(&self, tick: u64, dt: Duration) -> Snapshot {
+        Snapshot {
+            tick,
+            state: match self.play_state {
+                PlayState::MainMenu => "main_menu",
+                PlayState::MainGame => "main_game",
+            },
+            dt: dt.as_secs_f32() * 1000.0,
+            player: PlayerSnapshot {
+                x: self.player.x,
+                y: self.player.y,
+                hp: self.player.hp,
+                shield: self.player_status.shield,
+                lives: self.player_status.lives,
+                alt_cd: self.alt_cd,
+                dash_cd: self.player_status.dash_cd,
+            },
+            enemies: self
+                .enemy
+                .iter()
+                .map(|enemy| EnemySnapshot {
+                    id: enemy.id,
+                    kind: match enemy.actor_type {
+                        ActorType::Enemy(EnemyKind::Standard) => "Enemy".to_string(),
+                        ActorType::Enemy(kind) => format!("{:?}", kind),
+                        actor_type => format!("{:?}", actor_type),
+                    },
+                    x: enemy.x,
+                    y: enemy.y,
+                    vx: enemy.current_velocity.0,
+                    vy: enemy.current_velocity.1,
+                    hp: enemy.hp,
+                })
+                .collect(),
+            projectiles: self
+                .get_projectile_views()
+                .iter()
+                .map(|projectile| ProjectileSnapshot {
+                    x: projectile.position.0,
+                    y: projectile.position.1,
+                    vx: projectile.velocity.0,
+                    vy: projectile.velocity.1,
+                    faction: match projectile.faction {
+                        Faction::Player => "player",
+                        Faction::Enemy => "enemy",
+                    },
+                })
+                .collect(),
+            wave: WaveSnapshot {
+                number: *self.game_state_data.get("wave_count").unwrap_or(&1.0) as u32,
+                enemies_remaining: self.enemy.len(),
+                is_boss_wave: self.boss_state.is_some(),
+            },
+            kills: self.kills,
+            score: self.score,
+        }
+    }
+
+    /// Sends this tick's snapshot to the external bot and applies its input
+    fn exchange_with_bot(&mut self, ctx: &mut Context) {
+        if self.bot_server.is_none() {
+            return;
+        }
+        let dt = match self.is_in_lockstep() {
+            true => LOCKSTEP_DT,
+            false => ctx.time.delta(),
+        };
+        let tick = match &mut self.bot_server {
+            Some(server) => server.next_tick(),
+            None => return,
+        };
+        let snapshot = self.get_snapshot(tick, dt);
+        let inputs = match &mut self.bot_server {
+            Some(server) => server.exchange(&snapshot, self.dash_key),
+            None => vec![],
+        };
+        for input in inputs {
+            self.apply_bot_input(ctx, input);
+        }
+    }
+
     fn update_game(&mut self, ctx: &mut Context) -> GameResult {
-        self.dt = ctx.time.delta();
+        self.dt = match self.is_in_lockstep() {
+            true => LOCKSTEP_DT,
+            false => ctx.time.delta(),
+        };
+        self.game_time += self.dt;
         let screen_width = ctx.gfx.window().inner_size().width as f32;
         let screen_height = ctx.gfx.window().inner_size().height as f32;
         if self.alt_cd <= 0.0 {
@@ -398,113 +1062,475 @@ impl GameState {
         } else {
             self.alt_cd -= self.dt.as_millis() as f32;
         }
-        handle_player_movement(
-            &mut self.player,
-            &self.keys_pressed,
-            self.dt,
-            screen_width,
-            screen_height,
+        self.run_autopilot(ctx, screen_width, screen_height);
+        for remaining in self.pickup_effects.values_mut() {
+            *remaining -= self.dt.as_millis() as f32;
+        }
+        self.pickup_effects.retain(|_, remaining| *remaining > 0.0);
+        let is_shielded = self.pickup_effects.contains_key(&PickupType::Shield);
+        let player_settings = self.difficulty.player_settings();
+        handle_player_status(
+            &mut self.player_status,
+            &player_settings,
+            self.dt.as_secs_f32(),
+        );
+        self.director.update(
+            self.dt.as_millis() as f32,
+            self.player.hp / player_settings.max_hp,
+        );
+        self.game_state_data.insert(
+            "pickup_drop_chance".to_string(),
+            self.director.knobs.pickup_drop_chance,
         );
-        let mut player_coords = (self.player.x, self.player.y);
+
+        // Holding LMB keeps firing while rapid fire is active
+        if self.pickup_effects.contains_key(&PickupType::RapidFire) {
+            let rapid_fire_cd = *self.game_state_data.get("rapid_fire_cd").unwrap_or(&0.0)
+                - self.dt.as_millis() as f32;
+            if rapid_fire_cd <= 0.0
+                && self.player.hp > 0.0
+                && ctx.mouse.button_pressed(MouseButton::Left)
+            {
+                let mouse_position = ctx.mouse.position();
+                self.handle_mouse_input(ctx, MouseButton::Left, mouse_position.x, mouse_position.y);
+                self.game_state_data
+                    .insert("rapid_fire_cd".to_string(), 100.0);
+            } else {
+                self.game_state_data
+                    .insert("rapid_fire_cd".to_string(), rapid_fire_cd.max(0.0));
+            }
+        }
+        let previous_player_coords = (self.player.x, self.player.y);
+        // Slow fields cap how fast the player can move through them
+        let is_in_slow_field = self.hazards.iter().any(|hazard| {
+            hazard.actor_type == ActorType::Hazard(HazardKind::SlowField)
+                && is_in_range(
+                    hazard,
+                    previous_player_coords,
+                    HazardKind::SlowField.radius(),
+                )
+        });
+        if is_in_slow_field {
+            self.player.velocity = self.player.velocity.min(SLOW_FIELD_MAX_SPEED);
+        }
+        if self.player_status.is_dashing() {
+            handle_player_dash(
+                &mut self.player,
+                &mut self.player_status,
+                self.dt,
+                screen_width,
+                screen_height,
+            );
+        } else {
+            handle_player_movement(
+                &mut self.player,
+                &self.keys_pressed,
+                self.dt,
+                screen_width,
+                screen_height,
+            );
+        }
+        let player_coords = (self.player.x, self.player.y);
+        let player_agent = SteeringAgent {
+            position: player_coords,
+            velocity: match self.dt.as_secs_f32() > 0.0 {
+                true => (
+                    (player_coords.0 - previous_player_coords.0) / self.dt.as_secs_f32(),
+                    (player_coords.1 - previous_player_coords.1) / self.dt.as_secs_f32(),
+                ),
+                false => (0.0, 0.0),
+            },
+            max_speed: self.player.velocity,
+        };
+        let nearest_enemy = self
+            .enemy
+            .iter()
+            .map(|enemy| {
+                let distance = ((enemy.x - player_coords.0).powi(2)
+                    + (enemy.y - player_coords.1).powi(2))
+                .sqrt();
+                ((enemy.x, enemy.y), distance)
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(position, _)| position);
+        self.player_model.record_movement(
+            player_coords,
+            player_agent.velocity,
+            nearest_enemy,
+            self.dt.as_millis() as f32,
+        );
+
+        let boss_hp = self
+            .enemy
+            .iter()
+            .find(|enemy| enemy.actor_type == ActorType::BossEnemy)
+            .map(|boss| boss.hp);
+        if let Some(boss_state) = &mut self.boss_state {
+            let is_new_phase =
+                boss_state.update(boss_hp.unwrap_or(0.0), self.dt.as_millis() as f32);
+            if is_new_phase {
+                self.assets.special_atk.set_volume(0.6);
+                let res = self.assets.special_atk.play(ctx);
+                match res {
+                    Ok(_) => (),
+                    Err(e) => println!("Error playing boss phase sound: {:?}", e),
+                }
+            }
+            if boss_state.is_defeat_finished() {
+                self.boss_state = None;
+            }
+        }
+
+        self.launch_fighters(ctx);
+        let enemy_agents: Vec<SteeringAgent> = self
+            .enemy
+            .iter()
+            .map(|enemy| SteeringAgent {
+                position: (enemy.x, enemy.y),
+                velocity: enemy.current_velocity,
+                max_speed: enemy.velocity,
+            })
+            .collect();
+        let enemy_views: Vec<ActorView> = self
+            .enemy
+            .iter()
+            .map(|enemy| ActorView {
+                id: enemy.id,
+                position: (enemy.x, enemy.y),
+                velocity: enemy.current_velocity,
+                max_speed: enemy.velocity,
+                hp: enemy.hp,
+                attack_cooldown: enemy.attack_cooldown,
+            })
+            .collect();
+        let player_view = ActorView {
+            id: self.player.id,
+            position: player_coords,
+            velocity: player_agent.velocity,
+            max_speed: self.player.velocity,
+            hp: self.player.hp,
+            attack_cooldown: None,
+        };
+        let projectile_views = self.get_projectile_views();
+        let hazard_views: Vec<HazardView> = self
+            .hazards
+            .iter()
+            .filter_map(|hazard| match hazard.actor_type {
+                ActorType::Hazard(hazard_kind) => Some(HazardView {
+                    position: (hazard.x, hazard.y),
+                    radius: hazard_kind.radius(),
+                }),
+                _ => None,
+            })
+            .collect();
+        let wave_info = WaveInfo {
+            wave: *self.game_state_data.get("wave_count").unwrap_or(&1.0) as u32,
+            enemies_remaining: self.enemy.len(),
+            is_boss_wave: self.boss_state.is_some(),
+        };
+        // Bosses attack on their own schedule, everyone else shares the attack tokens
+        let squad_members: Vec<(EntityId, (f32, f32))> = self
+            .enemy
+            .iter()
+            .filter(|enemy| enemy.actor_type != ActorType::BossEnemy)
+            .map(|enemy| (enemy.id, (enemy.x, enemy.y)))
+            .collect();
+        let max_attack_tokens =
+            get_max_attack_tokens(*self.game_state_data.get("wave_count").unwrap_or(&1.0));
+        self.squad_coordinator.max_attack_tokens =
+            (max_attack_tokens as i32 + self.director.knobs.attack_token_offset).max(1) as usize;
+        self.squad_coordinator.flank_distance =
+            self.player_model.get_flank_distance(FLANK_DISTANCE);
+        self.squad_coordinator
+            .update(&squad_members, self.dt.as_millis() as f32);
 
         for i in 0..self.enemy.len() {
-            let enemy_velocity = self.enemy[i].velocity.clone();
-            let x = self.enemy[i].target_x;
-            let y = self.enemy[i].target_y;
+            let enemy_id = self.enemy[i].id;
+            let neighbors: Vec<SteeringAgent> = enemy_agents
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, agent)| *agent)
+                .collect();
+            if self.enemy[i].actor_type == ActorType::BossEnemy {
+                if let Some(boss_state) = &self.boss_state {
+                    self.enemy[i].velocity = boss_state.get_velocity();
+                    // Fly in from the top of the screen before the fight starts
+                    if boss_state.is_in_intro() {
+                        self.enemy[i].steering_goal =
+                            Some(SteeringGoal::Arrive((screen_width / 2.0, 200.0)));
+                        handle_enemy_movement(
+                            &mut self.enemy[i],
+                            self.dt,
+                            &neighbors,
+                            &player_agent,
+                        );
+                        continue;
+                    }
+                }
+            }
+            let allies: Vec<ActorView> = enemy_views
+                .iter()
+                .filter(|ally| {
+                    ally.id != enemy_id
+                        && ((ally.position.0 - enemy_views[i].position.0).powi(2)
+                            + (ally.position.1 - enemy_views[i].position.1).powi(2))
+                        .sqrt()
+                            < NEARBY_ALLY_RADIUS
+                })
+                .copied()
+                .collect();
+            let current_time = self.game_time.as_millis();
+            let (perceived_player, perceived_projectiles) =
+                match self.perceptions.get_mut(&enemy_id) {
+                    Some(perception) => perception.perceive(
+                        &enemy_views[i],
+                        &player_view,
+                        &projectile_views,
+                        current_time,
+                    ),
+                    None => (player_view, projectile_views.clone()),
+                };
+            // Enemies without a perception always know where the player is
+            let is_player_perceived = self
+                .perceptions
+                .get(&enemy_id)
+                .is_none_or(|perception| perception.awareness != Awareness::Unaware);
+            let perceived_player_agent = SteeringAgent {
+                position: perceived_player.position,
+                velocity: perceived_player.velocity,
+                max_speed: player_agent.max_speed,
+            };
+            let world = WorldView {
+                current_time,
+                actor: enemy_views[i],
+                player: perceived_player,
+                projectiles: &perceived_projectiles,
+                hazards: &hazard_views,
+                allies: &allies,
+                arena: (screen_width, screen_height),
+                wave: wave_info,
+            };
             match &mut self.enemy[i].ai {
                 Some(ai) => {
-                    let res = ai.as_mut().perform_action(
-                        ctx.time.time_since_start().as_millis(),
-                        player_coords,
-                        (x, y),
-                        enemy_velocity,
-                        self.projectiles
-                            .iter()
-                            .map(|projectile| (projectile.x, projectile.y))
-                            .collect(),
-                    );
-                    let res = match res {
-                        Ok(res) => res,
+                    let commands = match ai.as_mut().perform_action(&world) {
+                        Ok(commands) => commands,
                         Err(e) => {
                             println!("Error performing action: {:?}", e);
                             continue;
                         }
                     };
-                    self.enemy[i].target_x = res.enemy_position.0;
-                    self.enemy[i].target_y = res.enemy_position.1;
-                    player_coords = res.enemy_target;
-                    if res.is_attacking {
-                        self.attacking_enemies.push(i);
+                    let commands = match self.perceptions.get(&enemy_id) {
+                        Some(perception) => perception.filter_commands(commands),
+                        None => commands,
+                    };
+                    let mut aim_target = None;
+                    let mut weapon = None;
+                    let mut ram_target = None;
+                    let has_commands = !commands.is_empty();
+                    for command in commands {
+                        match command {
+                            AiCommand::Move(steering_goal) => {
+                                self.enemy[i].steering_goal = Some(steering_goal)
+                            }
+                            AiCommand::Aim(target) => aim_target = Some(target),
+                            AiCommand::Fire(weapon_id) => weapon = Some(weapon_id),
+                            AiCommand::Sound(cue) => self.play_sound_cue(ctx, cue),
+                            AiCommand::Ram(target) => ram_target = Some(target),
+                        }
+                    }
+                    // Shields follow what the AI is aiming at, or where it thinks the player is
+                    if let Some(shield) = self.shields.get_mut(&enemy_id) {
+                        shield.turn_toward(
+                            (self.enemy[i].x, self.enemy[i].y),
+                            aim_target.unwrap_or(perceived_player.position),
+                            self.dt.as_secs_f32() * 1000.0,
+                        );
+                    }
+                    let has_attack_token = self.squad_coordinator.has_attack_token(enemy_id);
+                    let may_attack =
+                        has_attack_token || self.enemy[i].actor_type == ActorType::BossEnemy;
+                    match weapon.filter(|_| is_player_perceived && may_attack) {
+                        // Rewritten every time the AI fires so the shot goes where it aims now
+                        Some(weapon) => {
+                            self.attacking_enemies.insert(
+                                enemy_id,
+                                AttackOrder {
+                                    weapon,
+                                    target: aim_target.unwrap_or(perceived_player.position),
+                                },
+                            );
+                        }
+                        // AIs stay quiet between decisions, so the order only goes stale once
+                        // the enemy decides on something else, loses its token or the player
+                        None if has_commands || !may_attack || !is_player_perceived => {
+                            self.attacking_enemies.remove(&enemy_id);
+                        }
+                        None => (),
+                    }
+                    if let Some(target) = ram_target {
+                        if has_attack_token && !self.rams.contains_key(&enemy_id) {
+                            self.rams.insert(
+                                enemy_id,
+                                RamAttack::new(
+                                    (self.enemy[i].x, self.enemy[i].y),
+                                    target,
+                                    (screen_width, screen_height),
+                                ),
+                            );
+                            self.play_sound_cue(ctx, SoundCue::Charge);
+                        }
+                    }
+                    // Enemies waiting for a token hold their flank instead of closing in
+                    let wants_player = weapon.is_some()
+                        || ram_target.is_some()
+                        || self.enemy[i].steering_goal == Some(SteeringGoal::PursuePlayer);
+                    if !has_attack_token && wants_player && is_player_perceived {
+                        if let Some(flank) = self
+                            .squad_coordinator
+                            .get_flanking_position(enemy_id, perceived_player.position)
+                        {
+                            self.enemy[i].steering_goal = Some(SteeringGoal::Arrive(flank));
+                        }
                     }
                 }
                 None => (),
             };
+            // Fighters that lost their carrier break off and flee until they self-destruct
+            if self.orphaned_fighters.contains_key(&enemy_id) {
+                self.enemy[i].steering_goal = Some(SteeringGoal::EvadePlayer);
+                self.attacking_enemies.remove(&enemy_id);
+            }
+            if self.enemy[i].actor_type == ActorType::BossEnemy {
+                if let Some(boss_state) = &self.boss_state {
+                    let movement_target = get_boss_movement_target(
+                        boss_state.current_phase().movement,
+                        (self.enemy[i].x, self.enemy[i].y),
+                        (self.player.x, self.player.y),
+                        boss_state.elapsed,
+                        screen_width,
+                    );
+                    if let Some(target) = movement_target {
+                        self.enemy[i].steering_goal = Some(SteeringGoal::Arrive(target));
+                    }
+                    // Bosses fire their pattern whenever it is off cooldown
+                    self.attacking_enemies.insert(
+                        enemy_id,
+                        AttackOrder {
+                            weapon: WeaponId::Blaster,
+                            target: perceived_player.position,
+                        },
+                    );
+                }
+            }
 
-            handle_enemy_movement(&mut self.enemy[i], self.dt);
+            let ram_velocity = match self.rams.get_mut(&enemy_id) {
+                Some(ram) => ram.update(self.dt.as_secs_f32() * 1000.0),
+                None => None,
+            };
+            match ram_velocity {
+                // Rams ignore steering and go straight along their locked vector
+                Some(velocity) => {
+                    self.enemy[i].current_velocity = velocity;
+                    self.enemy[i].x += velocity.0 * self.dt.as_secs_f32();
+                    self.enemy[i].y += velocity.1 * self.dt.as_secs_f32();
+                }
+                None => {
+                    self.rams.remove(&enemy_id);
+                    handle_enemy_movement(
+                        &mut self.enemy[i],
+                        self.dt,
+                        &neighbors,
+                        &perceived_player_agent,
+                    );
+                }
+            }
 
-            if self.enemy[i].attack_cooldown == Some(0.0) && self.attacking_enemies.contains(&i) {
-                self.attacking_enemies.retain(|&x| x != i);
-                let mut aim_x = player_coords.0 + rand::thread_rng().gen_range(-420.0..420.0);
-                let mut aim_y = player_coords.1 + rand::thread_rng().gen_range(-420.0..420.0);
+            let attack_order = match self.enemy[i].attack_cooldown == Some(0.0) {
+                true => self.attacking_enemies.remove(&enemy_id),
+                false => None,
+            };
+            if let Some(attack_order) = attack_order {
+                let mut target = attack_order.target;
+                // Lead the shot to where this player usually moves
+                if self.enemy[i].actor_type != ActorType::BossEnemy {
+                    let aim_offset = self.player_model.get_aim_offset(
+                        (self.enemy[i].x, self.enemy[i].y),
+                        target,
+                        ENEMY_PROJECTILE_SPEED,
+                    );
+                    target = (target.0 + aim_offset.0, target.1 + aim_offset.1);
+                }
+                let mut aim_x = target.0 + rand::thread_rng().gen_range(-420.0..420.0);
+                let mut aim_y = target.1 + rand::thread_rng().gen_range(-420.0..420.0);
 
                 if self.enemy[i].actor_type == ActorType::BossEnemy {
-                    aim_x = player_coords.0 + rand::thread_rng().gen_range(-69.0..69.0);
-                    aim_y = player_coords.1 + rand::thread_rng().gen_range(-69.0..69.0);
+                    aim_x = target.0 + rand::thread_rng().gen_range(-69.0..69.0);
+                    aim_y = target.1 + rand::thread_rng().gen_range(-69.0..69.0);
                 }
 
                 let direction = ((aim_x - self.enemy[i].x), (aim_y - self.enemy[i].y));
-                // Calculate the length of the direction vector
-                let length = (direction.0.powi(2) + direction.1.powi(2)).sqrt();
-                // Normalize the direction vector to get a unit direction vector
-                let unit_direction = match length > 0.0 {
-                    true => (direction.0 / length, direction.1 / length),
-                    false => (0.0, 0.0),
-                };
-                // Multiply the unit direction vector by a large number to get a far away target position
-                let far_away_target = (
-                    player_coords.0 + unit_direction.0 * 10000.0,
-                    player_coords.1 + unit_direction.1 * 10000.0,
-                );
+                let base_angle = direction.1.atan2(direction.0);
                 let mut projectiles = vec![];
-                if self.enemy[i].actor_type == ActorType::BossEnemy {
-                    let boss_kills = self.game_state_data.get("boss_count");
-                    let boss_kills = match boss_kills {
-                        Some(count) => (count * 5.0) as u32,
-                        None => 5u32,
-                    };
-                    for j in 0..=boss_kills {
-                        let mut offset = j as f32 * 200.0;
-                        if j % 2 == 0 {
-                            offset *= -1.0;
+                if let (ActorType::BossEnemy, Some(boss_state)) =
+                    (self.enemy[i].actor_type, &mut self.boss_state)
+                {
+                    let pattern_name = self
+                        .player_model
+                        .pick_pattern(&boss_state.current_phase().pattern, &mut thread_rng());
+                    match self.assets.bullet_patterns.get(&pattern_name) {
+                        Some(pattern) => {
+                            self.player_model.record_pattern(&pattern_name);
+                            // The cooldown is paused until the pattern has finished playing
+                            boss_state.pattern_emitter = Some(PatternEmitter::new(pattern.clone()));
+                            self.enemy[i].attack_cooldown = None;
                         }
-                        let projectile_type = j % 3;
-                        let projectile = match projectile_type {
-                            0 => {
-                                let projectile = create_boss_enemy_projectile(
-                                    self.enemy[i].x,
-                                    self.enemy[i].y,
-                                    far_away_target.0 + offset,
-                                    far_away_target.1 + offset,
-                                    create_boss_enemy_projectile_mesh(ctx),
-                                    None,
-                                    Some(5.0),
-                                );
-                                projectile
-                            }
-                            _ => {
-                                let projectile = create_enemy_projectile(
-                                    self.enemy[i].x,
-                                    self.enemy[i].y,
-                                    far_away_target.0 + offset,
-                                    far_away_target.1 + offset,
-                                    create_enemy_projectile_mesh(ctx),
-                                    None,
-                                    Some(1.0),
-                                );
-                                projectile
-                            }
-                        };
-                        projectiles.push(projectile);
+                        None => {
+                            println!("Unknown bullet pattern: {}", pattern_name);
+                            self.enemy[i].attack_cooldown = Some(boss_state.get_attack_cooldown());
+                        }
+                    }
+                } else {
+                    let spread_angles: &[f32] = match attack_order.weapon {
+                        WeaponId::Blaster => &[0.0],
+                        WeaponId::Spread => &[-15.0, 0.0, 15.0],
+                    };
+                    for spread_angle in spread_angles {
+                        let angle = base_angle + spread_angle.to_radians();
+                        // Aim at a far away point so the projectile keeps going past the target
+                        let far_away_target = (
+                            self.enemy[i].x + angle.cos() * 10000.0,
+                            self.enemy[i].y + angle.sin() * 10000.0,
+                        );
+                        projectiles.push(create_enemy_projectile(
+                            self.enemy[i].x,
+                            self.enemy[i].y,
+                            far_away_target.0,
+                            far_away_target.1,
+                            create_enemy_projectile_mesh(ctx),
+                            None,
+                            Some(1.0),
+                        ));
+                    }
+                    self.play_sound_cue(ctx, SoundCue::Laser);
+                    self.player_model
+                        .record_volley((self.enemy[i].x, self.enemy[i].y), player_coords);
+                    self.enemy[i].attack_cooldown = Some(1000.0);
+                }
+                self.projectiles.extend(projectiles);
+            }
+
+            if let (ActorType::BossEnemy, Some(boss_state)) =
+                (self.enemy[i].actor_type, &mut self.boss_state)
+            {
+                if let Some(emitter) = &mut boss_state.pattern_emitter {
+                    let origin = (self.enemy[i].x, self.enemy[i].y);
+                    let shots = emitter.update(
+                        self.dt.as_millis() as f32,
+                        origin,
+                        (self.player.x, self.player.y),
+                    );
+                    if !shots.is_empty() {
                         self.assets.special_atk.set_volume(0.4);
                         let res = self.assets.special_atk.play(ctx);
                         match res {
@@ -512,33 +1538,48 @@ impl GameState {
                             Err(e) => println!("Error special atk sound: {:?}", e),
                         };
                     }
-                    self.enemy[i].attack_cooldown = Some(100.0);
-                } else {
-                    let projectile = create_enemy_projectile(
-                        self.enemy[i].x,
-                        self.enemy[i].y,
-                        far_away_target.0,
-                        far_away_target.1,
-                        create_enemy_projectile_mesh(ctx),
-                        None,
-                        Some(1.0),
-                    );
-
-                    self.assets.laser_1.set_volume(0.4);
-                    let res = self.assets.laser_1.play(ctx);
-                    match res {
-                        Ok(_) => (),
-                        Err(e) => println!("Error playing laser_1: {:?}", e),
+                    for shot in shots {
+                        let mut projectile = match shot.heavy {
+                            true => create_boss_enemy_projectile(
+                                origin.0,
+                                origin.1,
+                                shot.target.0,
+                                shot.target.1,
+                                create_boss_enemy_projectile_mesh(ctx),
+                                None,
+                                Some(5.0),
+                            ),
+                            false => create_enemy_projectile(
+                                origin.0,
+                                origin.1,
+                                shot.target.0,
+                                shot.target.1,
+                                create_enemy_projectile_mesh(ctx),
+                                None,
+                                Some(1.0),
+                            ),
+                        };
+                        if let Some(speed) = shot.speed {
+                            projectile.velocity = speed;
+                        }
+                        projectile.projectile_modifier = shot.modifier;
+                        self.projectiles.push(projectile);
+                    }
+                    if emitter.is_finished() {
+                        boss_state.pattern_emitter = None;
+                        self.enemy[i].attack_cooldown = Some(boss_state.get_attack_cooldown());
                     }
-                    projectiles.push(projectile);
-                    self.enemy[i].attack_cooldown = Some(1000.0);
                 }
-                self.projectiles.extend(projectiles);
             }
         }
         for projectile in &mut self.projectiles {
             // Check if the projectile is outside the screen boundaries
             handle_timed_life(projectile, self.dt.as_secs_f32());
+            handle_projectile_modifier(
+                projectile,
+                self.dt.as_millis() as f32,
+                (self.player.x, self.player.y),
+            );
             handle_projectile_trajectory(projectile, self.dt);
             // Check if the projectile is outside the screen boundaries
             if projectile.x <= 0.0 - 500.0
@@ -550,20 +1591,42 @@ impl GameState {
             }
         }
 
+        let mut weapon_level_ups = 0;
+        let health_before_collisions = self.player.hp + self.player_status.shield;
+        let hazard_kills = self.update_hazards(ctx);
+        let is_boss_in_intro = self
+            .boss_state
+            .as_ref()
+            .is_some_and(|boss_state| boss_state.is_in_intro());
+        let mut blast_positions = vec![];
+        let mut splits = vec![];
+        let mut deflections = 0;
         for enemy in &mut self.enemy {
+            let mut is_detonated_on_contact = false;
             // Check for collisions between the player and the enemy
             let distance =
                 ((enemy.x - self.player.x).powi(2) + (enemy.y - self.player.y).powi(2)).sqrt();
             let player_image = self.player.image.clone().unwrap();
-            if distance
+            let is_touching_player = distance
                 < player_image
                     .dimensions(ctx)
                     .expect("Failed to get player image dimensions")
-                    .w
-            {
+                    .w;
+            if is_touching_player && enemy.actor_type == ActorType::Enemy(EnemyKind::Kamikaze) {
+                // The blast below does the damage
+                enemy.hp = 0.0;
+                is_detonated_on_contact = true;
+            } else if is_touching_player {
                 let player_hp = self.player.hp.clone();
                 let enemy_hp = &enemy.hp.clone();
-                take_damage(&mut self.player, enemy_hp);
+                if !is_shielded {
+                    absorb_damage(
+                        &mut self.player,
+                        &mut self.player_status,
+                        &player_settings,
+                        enemy_hp,
+                    );
+                }
                 take_damage(enemy, &player_hp);
 
                 self.assets.damage.set_volume(0.5);
@@ -574,10 +1637,14 @@ impl GameState {
                 }
             }
             // Check for collisions between the enemy and the projectiles
+            let mut impact_direction = None;
             for projectile in &mut self.projectiles {
                 if projectile.actor_type == ActorType::EnemyProjectile {
                     continue;
                 }
+                if is_boss_in_intro && enemy.actor_type == ActorType::BossEnemy {
+                    continue;
+                }
                 let distance =
                     ((projectile.x - enemy.x).powi(2) + (projectile.y - enemy.y).powi(2)).sqrt();
                 match &enemy.image {
@@ -588,8 +1655,28 @@ impl GameState {
                                 .expect("Failed to get enemy image dimensions")
                                 .w
                         {
+                            let shield_hit = match self.shields.get(&enemy.id) {
+                                Some(shield) => shield.on_hit((enemy.x, enemy.y), projectile),
+                                None => ShieldHit::Open,
+                            };
+                            match shield_hit {
+                                ShieldHit::Deflected => {
+                                    projectile.bounding_box = create_enemy_projectile_mesh(ctx);
+                                    deflections += 1;
+                                    continue;
+                                }
+                                ShieldHit::Broken => {
+                                    self.shields.remove(&enemy.id);
+                                }
+                                ShieldHit::Open => (),
+                            }
                             let hp = enemy.hp.clone();
                             take_damage(enemy, &projectile.hp);
+                            self.director.record_hit();
+                            impact_direction = Some((
+                                projectile.target_x - projectile.x,
+                                projectile.target_y - projectile.y,
+                            ));
                             projectile.hp -= hp;
                             if projectile.hp <= 0.0 {
                                 projectile.hp = 0.0;
@@ -604,8 +1691,28 @@ impl GameState {
                                 .expect("Failed to get bounding box dimensions")
                                 .w
                         {
+                            let shield_hit = match self.shields.get(&enemy.id) {
+                                Some(shield) => shield.on_hit((enemy.x, enemy.y), projectile),
+                                None => ShieldHit::Open,
+                            };
+                            match shield_hit {
+                                ShieldHit::Deflected => {
+                                    projectile.bounding_box = create_enemy_projectile_mesh(ctx);
+                                    deflections += 1;
+                                    continue;
+                                }
+                                ShieldHit::Broken => {
+                                    self.shields.remove(&enemy.id);
+                                }
+                                ShieldHit::Open => (),
+                            }
                             let hp = enemy.hp.clone();
                             take_damage(enemy, &projectile.hp);
+                            self.director.record_hit();
+                            impact_direction = Some((
+                                projectile.target_x - projectile.x,
+                                projectile.target_y - projectile.y,
+                            ));
                             projectile.hp -= hp;
                             if projectile.hp <= 0.0 {
                                 projectile.hp = 0.0;
@@ -615,35 +1722,177 @@ impl GameState {
                 }
             }
             if enemy.hp <= 0.0 {
+                if enemy.actor_type == ActorType::Enemy(EnemyKind::Kamikaze) {
+                    blast_positions.push((enemy.x, enemy.y));
+                }
+                if let Some(splitter) = self.splitters.get(&enemy.id) {
+                    if splitter.depth < self.assets.enemy_settings.splitter.max_depth {
+                        // Rammed splitters burst away from the player instead
+                        let direction = impact_direction
+                            .unwrap_or((enemy.x - self.player.x, enemy.y - self.player.y));
+                        splits.push((
+                            enemy.x,
+                            enemy.y,
+                            direction,
+                            enemy.attack_cooldown,
+                            *splitter,
+                        ));
+                    }
+                }
+                // Kamikazes that blow up on the player and enemies caught in hazards earn nothing
+                if is_detonated_on_contact || hazard_kills.contains(&enemy.id) {
+                    continue;
+                }
+                // The carrier already paid for its fighters when it went down
+                if self.orphaned_fighters.contains_key(&enemy.id) {
+                    continue;
+                }
+                let score_multiplier = match self
+                    .pickup_effects
+                    .contains_key(&PickupType::ScoreMultiplier)
+                {
+                    true => 2,
+                    false => 1,
+                };
                 if enemy.actor_type == ActorType::BossEnemy {
                     self.kills += 10;
+                    self.score += 10 * score_multiplier;
                     let count = match self.game_state_data.get("boss_count") {
                         Some(count) => count + 1.0,
                         None => 1.0,
                     };
                     self.game_state_data.insert("boss_count".to_string(), count);
+                    if let Some(boss_state) = &mut self.boss_state {
+                        boss_state.start_defeat();
+                    }
                 } else {
+                    // A carrier pays up front for the fighters it still has out. They flee and
+                    // self-destruct afterwards, and shooting them down earns nothing more.
+                    let fighters = self
+                        .carriers
+                        .get(&enemy.id)
+                        .map_or(0, |carrier| carrier.fighters.len() as u64);
                     self.kills += 1;
+                    self.score += (1 + fighters) * score_multiplier;
                 }
+                self.director.record_kill();
                 if self.kills % 20 == 0 {
-                    let level = self.game_state_data.get("weapon_level");
-                    let level = match level {
-                        Some(level) => {
-                            if level >= &5.0 {
-                                5.0
-                            } else if level >= &3.0 {
-                                level + 0.25
-                            } else {
-                                level + 1.0
-                            }
-                        }
-                        None => 2.0,
-                    };
-                    self.game_state_data
-                        .insert("weapon_level".to_string(), level);
+                    weapon_level_ups += 1;
+                }
+                let drop_chance = *self
+                    .game_state_data
+                    .get("pickup_drop_chance")
+                    .unwrap_or(&DEFAULT_PICKUP_DROP_CHANCE);
+                if let Some(pickup_type) = roll_pickup_drop(drop_chance) {
+                    self.pickups.push(create_pickup(
+                        enemy.x,
+                        enemy.y,
+                        pickup_type,
+                        create_pickup_mesh(ctx, pickup_type.color()),
+                    ));
+                }
+            }
+        }
+        for _ in 0..weapon_level_ups {
+            self.upgrade_weapon_level();
+        }
+        if deflections > 0 {
+            self.play_sound_cue(ctx, SoundCue::Deflect);
+        }
+        for blast in &mut self.blasts {
+            blast.remaining -= self.dt.as_secs_f32() * 1000.0;
+        }
+        self.blasts.retain(|blast| blast.remaining > 0.0);
+        for (x, y) in blast_positions {
+            let distance = ((x - self.player.x).powi(2) + (y - self.player.y).powi(2)).sqrt();
+            if !is_shielded && distance < KAMIKAZE_BLAST_RADIUS {
+                absorb_damage(
+                    &mut self.player,
+                    &mut self.player_status,
+                    &player_settings,
+                    &KAMIKAZE_BLAST_DAMAGE,
+                );
+            }
+            self.assets.damage.set_volume(0.7);
+            let res = self.assets.damage.play(ctx);
+            match res {
+                Ok(_) => (),
+                Err(e) => println!("Error playing blast sound: {:?}", e),
+            }
+            self.blasts.push(Blast {
+                x,
+                y,
+                radius: KAMIKAZE_BLAST_RADIUS,
+                remaining: BLAST_DURATION,
+            });
+        }
+        let split_settings = self.assets.enemy_settings.splitter;
+        for (x, y, direction, attack_cd, splitter) in splits {
+            let depth = splitter.depth + 1;
+            let max_hp = splitter.max_hp * split_settings.scale;
+            for heading in get_split_directions(direction, split_settings.count) {
+                let (ai, perception_profile) = self.create_splitter_ai(splitter.personality);
+                // start the pieces a little apart so they don't sit on top of each other
+                let mut piece = create_splitter_enemy(
+                    x + heading.0 * 20.0,
+                    y + heading.1 * 20.0,
+                    create_splitter_spaceship_mesh(ctx, split_settings.scale.powi(depth as i32)),
+                    Some(self.kills as f32 * 1.10),
+                    attack_cd,
+                    Some(ai),
+                );
+                // pieces get their hp from the splitter they came from
+                piece.hp = max_hp;
+                piece.velocity *= split_settings.speed_up.powi(depth as i32);
+                piece.current_velocity = (
+                    heading.0 * split_settings.scatter_speed,
+                    heading.1 * split_settings.scatter_speed,
+                );
+                self.splitters.insert(
+                    piece.id,
+                    Splitter {
+                        depth,
+                        max_hp,
+                        personality: splitter.personality,
+                    },
+                );
+                let perception = Perception::new(
+                    self.assets
+                        .perception_profiles
+                        .get_profile(perception_profile),
+                );
+                self.perceptions.insert(piece.id, perception);
+                self.enemy.push(piece);
+            }
+        }
+
+        let mut collected_pickups = vec![];
+        for pickup in &mut self.pickups {
+            handle_timed_life(pickup, self.dt.as_secs_f32());
+            handle_projectile_trajectory(pickup, self.dt);
+            if pickup.y >= screen_height + 50.0 {
+                pickup.hp = 0.0;
+            }
+            // Check for collisions between the player and the pickup
+            let distance =
+                ((pickup.x - self.player.x).powi(2) + (pickup.y - self.player.y).powi(2)).sqrt();
+            let player_image = self.player.image.clone().unwrap();
+            if self.player.hp > 0.0
+                && distance
+                    < player_image
+                        .dimensions(ctx)
+                        .expect("Failed to get player image dimensions")
+                        .w
+            {
+                if let ActorType::Pickup(pickup_type) = pickup.actor_type {
+                    collected_pickups.push(pickup_type);
                 }
+                pickup.hp = 0.0;
             }
         }
+        for pickup_type in collected_pickups {
+            self.apply_pickup(ctx, pickup_type);
+        }
 
         // Check for collisions between the player and the projectiles
         for projectile in &mut self.projectiles {
@@ -659,7 +1908,14 @@ impl GameState {
             {
                 if projectile.actor_type == ActorType::EnemyProjectile {
                     let hp = self.player.hp;
-                    take_damage(&mut self.player, &projectile.hp);
+                    if !is_shielded {
+                        absorb_damage(
+                            &mut self.player,
+                            &mut self.player_status,
+                            &player_settings,
+                            &projectile.hp,
+                        );
+                    }
 
                     self.assets.damage.set_volume(0.5);
                     let res = self.assets.damage.play(ctx);
@@ -675,9 +1931,85 @@ impl GameState {
             }
         }
 
+        let damage_taken = health_before_collisions - (self.player.hp + self.player_status.shield);
+        if damage_taken > 0.0 {
+            self.director.record_damage_taken(damage_taken);
+            if self.boss_state.is_some() && !is_boss_in_intro {
+                self.player_model.record_pattern_damage(damage_taken);
+            }
+        }
+        if self.player.hp <= 0.0 && self.player_status.lives > 0 {
+            // lives counts the ship in play, so losing the last one is game over
+            self.player_status.lives -= 1;
+            if self.player_status.lives > 0 {
+                self.respawn_player(screen_width, screen_height);
+            }
+        }
+
+        for remaining in self.orphaned_fighters.values_mut() {
+            *remaining -= self.dt.as_secs_f32() * 1000.0;
+        }
+        for enemy in &mut self.enemy {
+            let is_self_destructing = self
+                .orphaned_fighters
+                .get(&enemy.id)
+                .is_some_and(|remaining| *remaining <= 0.0);
+            if is_self_destructing && enemy.hp > 0.0 {
+                // Gone without counting as a kill
+                enemy.hp = 0.0;
+                self.blasts.push(Blast {
+                    x: enemy.x,
+                    y: enemy.y,
+                    radius: KAMIKAZE_BLAST_RADIUS,
+                    remaining: BLAST_DURATION,
+                });
+            }
+        }
+
         self.projectiles.retain(|projectile| projectile.hp > 0.0);
+        self.pickups.retain(|pickup| pickup.hp > 0.0);
         self.enemy.retain(|enemy| enemy.hp > 0.0);
-        if self.enemy.is_empty() {
+        let enemy_ids: HashSet<EntityId> = self.enemy.iter().map(|enemy| enemy.id).collect();
+        for (carrier_id, carrier) in &mut self.carriers {
+            carrier.fighters.retain(|id| enemy_ids.contains(id));
+            if !enemy_ids.contains(carrier_id) {
+                for fighter in &carrier.fighters {
+                    self.orphaned_fighters
+                        .insert(*fighter, FIGHTER_ORPHAN_DURATION);
+                }
+            }
+        }
+        self.carriers.retain(|id, _| enemy_ids.contains(id));
+        self.orphaned_fighters
+            .retain(|id, _| enemy_ids.contains(id));
+        self.splitters.retain(|id, _| enemy_ids.contains(id));
+        self.shields.retain(|id, _| enemy_ids.contains(id));
+        self.mine_layers.retain(|id, _| enemy_ids.contains(id));
+        self.attacking_enemies
+            .retain(|id, _| enemy_ids.contains(id));
+        self.perceptions.retain(|id, _| enemy_ids.contains(id));
+        self.rams.retain(|id, _| enemy_ids.contains(id));
+        let is_boss_defeat_playing = self
+            .boss_state
+            .as_ref()
+            .is_some_and(|boss_state| boss_state.is_defeated());
+        let mut is_next_wave_due = false;
+        if self.enemy.is_empty() && !is_boss_defeat_playing {
+            // The director decides how long the player gets to breathe between waves
+            let wave_delay_cd = *self
+                .game_state_data
+                .get("wave_delay_cd")
+                .unwrap_or(&self.director.knobs.wave_delay)
+                - self.dt.as_millis() as f32;
+            is_next_wave_due = wave_delay_cd <= 0.0;
+            match is_next_wave_due {
+                true => self.game_state_data.remove("wave_delay_cd"),
+                false => self
+                    .game_state_data
+                    .insert("wave_delay_cd".to_string(), wave_delay_cd),
+            };
+        }
+        if is_next_wave_due {
             let wave_count = self.game_state_data.get("wave_count");
             let wave_count = match wave_count {
                 Some(count) => count + &1.0,
@@ -686,40 +2018,33 @@ impl GameState {
             self.game_state_data
                 .insert("wave_count".to_string(), wave_count);
             let mut is_boss_round = false;
-            let boss_count = self.game_state_data.get("boss_count");
-            let boss_count = match boss_count {
-                Some(count) => count + &1.0,
-                None => 1.0,
-            };
+            // boss_count tracks defeated bosses, so the next boss is one past it
+            let bosses_defeated = *self.game_state_data.get("boss_count").unwrap_or(&0.0);
+            let boss_count = bosses_defeated + 1.0;
             let is_eligible_for_boss = wave_count % 5.0 == 0.0;
             if is_eligible_for_boss {
-                let ai_to_use: Box<dyn EnemyAi> = match boss_count {
-                    boss_count if boss_count % 2.0 == 0.0 => {
-                        let ai = AggressiveEnemyAI::new();
-                        Box::new(ai)
-                    }
-                    _ => {
-                        let ai = NormalEnemyAI::new();
-                        Box::new(ai)
-                    }
-                };
+                let boss_definitions = &self.assets.boss_definitions;
+                let definition =
+                    boss_definitions[bosses_defeated as usize % boss_definitions.len()].clone();
 
                 is_boss_round = true;
-                self.enemy.push(create_boss_enemy(
-                    900.0,
-                    500.0,
+                let boss = create_boss_enemy(
+                    screen_width / 2.0,
+                    -100.0,
                     Color::RED,
+                    &definition,
                     boss_count * 1.75,
                     wave_count * 1.05,
                     create_boss_enemy_spaceship_mesh(ctx),
                     Some(self.assets.boss_ship.clone()),
                     Some(0_f32),
-                    Some(ai_to_use),
-                ));
-                self.game_state_data
-                    .insert("boss_count".to_string(), boss_count);
+                    Some(create_enemy_ai(definition.behavior_tree)),
+                );
+                self.boss_state = Some(BossState::new(definition, boss.hp, boss.velocity));
+                self.enemy.push(boss);
             } else {
-                for i in 0..(wave_count * 1.75).ceil() as u32 {
+                for i in 0..(wave_count * 1.75 * self.director.knobs.wave_size_scale).ceil() as u32
+                {
                     let mut rng = rand::thread_rng();
                     let mut x_nums: Vec<i32> = (0..1800).collect();
                     let mut y_nums: Vec<i32> = (100..900).collect();
@@ -740,31 +2065,153 @@ impl GameState {
                     } else {
                         None
                     };
-                    let ai_to_use: Box<dyn EnemyAi> = match i {
-                        i if i % 5 == 0 => {
-                            let ai = AggressiveEnemyAI::new();
-                            Box::new(ai)
-                        }
-                        i if i % 10 == 0 => {
-                            let ai = ElusiveEnemyAI::new();
-                            Box::new(ai)
-                        }
-                        _ => {
-                            let ai = NormalEnemyAI::new();
-                            Box::new(ai)
-                        }
+                    let splitter_personality = match i % 3 {
+                        0 => BehaviorTreeType::AggressiveEnemy,
+                        1 => BehaviorTreeType::ElusiveEnemy,
+                        _ => BehaviorTreeType::NormalEnemy,
                     };
+                    // the perception profile goes with the kind of AI
+                    let (ai_to_use, perception_profile, kind): (Box<dyn EnemyAi>, &str, EnemyKind) =
+                        match i {
+                            _ if rng.gen::<f32>() < get_kamikaze_spawn_chance(wave_count) => (
+                                Box::new(KamikazeEnemyAI::new()),
+                                "aggressive",
+                                EnemyKind::Kamikaze,
+                            ),
+                            _ if rng.gen::<f32>() < get_mine_layer_spawn_chance(wave_count) => {
+                                let ai = NormalEnemyAI::from_timing(
+                                    self.assets.action_timings.get_profile("normal"),
+                                );
+                                (Box::new(ai), "normal", EnemyKind::MineLayer)
+                            }
+                            _ if rng.gen::<f32>() < get_splitter_spawn_chance(wave_count) => {
+                                let (ai, profile) = self.create_splitter_ai(splitter_personality);
+                                (ai, profile, EnemyKind::Splitter)
+                            }
+                            _ if rng.gen::<f32>() < get_carrier_spawn_chance(wave_count) => {
+                                let ai = ElusiveEnemyAI::from_timing(
+                                    self.assets.action_timings.get_profile("elusive"),
+                                );
+                                (Box::new(ai), "elusive", EnemyKind::Carrier)
+                            }
+                            _ if rng.gen::<f32>() < self.director.knobs.aggressive_share => {
+                                let ai = AggressiveEnemyAI::from_timing(
+                                    self.assets.action_timings.get_profile("aggressive"),
+                                );
+                                (Box::new(ai), "aggressive", EnemyKind::Standard)
+                            }
+                            i if i % 5 == 0 => {
+                                let ai = AggressiveEnemyAI::from_timing(
+                                    self.assets.action_timings.get_profile("aggressive"),
+                                );
+                                (Box::new(ai), "aggressive", EnemyKind::Standard)
+                            }
+                            i if i % 10 == 0 => {
+                                let ai = ElusiveEnemyAI::from_timing(
+                                    self.assets.action_timings.get_profile("elusive"),
+                                );
+                                (Box::new(ai), "elusive", EnemyKind::Standard)
+                            }
+                            i if i % 4 == 0 => {
+                                let definitions: Vec<&HfsmDefinition> =
+                                    self.assets.hfsm_definitions.values().collect();
+                                // An empty hfsm_enemies.toml leaves only the normal AI to use
+                                let ai: Box<dyn EnemyAi> = match definitions.is_empty() {
+                                    true => Box::new(NormalEnemyAI::from_timing(
+                                        self.assets.action_timings.get_profile("normal"),
+                                    )),
+                                    false => {
+                                        let definition =
+                                            definitions[rng.gen_range(0..definitions.len())];
+                                        Box::new(HfsmEnemyAI::from_definition(definition.clone()))
+                                    }
+                                };
+                                (ai, "normal", EnemyKind::Standard)
+                            }
+                            i if i % 3 == 0 => {
+                                let ai =
+                                    UtilityEnemyAI::from_config(self.assets.utility_curves.clone());
+                                (Box::new(ai), "normal", EnemyKind::Standard)
+                            }
+                            _ => {
+                                let ai = NormalEnemyAI::from_timing(
+                                    self.assets.action_timings.get_profile("normal"),
+                                );
+                                (Box::new(ai), "normal", EnemyKind::Standard)
+                            }
+                        };
 
-                    let enemy = create_enemy(
-                        x_nums[0] as f32,
-                        y_nums[0] as f32,
-                        Color::RED,
-                        create_enemy_spaceship_mesh(ctx),
-                        None,
-                        Some(self.kills as f32 * 1.10),
-                        attack_cd,
-                        Some(ai_to_use),
+                    let enemy = match kind {
+                        EnemyKind::Kamikaze => create_kamikaze_enemy(
+                            x_nums[0] as f32,
+                            y_nums[0] as f32,
+                            create_kamikaze_spaceship_mesh(ctx),
+                            Some(self.kills as f32 * 1.10),
+                            Some(ai_to_use),
+                        ),
+                        EnemyKind::Carrier => create_carrier_enemy(
+                            x_nums[0] as f32,
+                            y_nums[0] as f32,
+                            create_carrier_spaceship_mesh(ctx),
+                            Some(self.kills as f32 * 1.10),
+                            attack_cd,
+                            Some(ai_to_use),
+                        ),
+                        EnemyKind::MineLayer => create_mine_layer_enemy(
+                            x_nums[0] as f32,
+                            y_nums[0] as f32,
+                            create_mine_layer_spaceship_mesh(ctx),
+                            Some(self.kills as f32 * 1.10),
+                            attack_cd,
+                            Some(ai_to_use),
+                        ),
+                        EnemyKind::Splitter => create_splitter_enemy(
+                            x_nums[0] as f32,
+                            y_nums[0] as f32,
+                            create_splitter_spaceship_mesh(ctx, 1.0),
+                            Some(self.kills as f32 * 1.10),
+                            attack_cd,
+                            Some(ai_to_use),
+                        ),
+                        _ => create_enemy(
+                            x_nums[0] as f32,
+                            y_nums[0] as f32,
+                            Color::RED,
+                            create_enemy_spaceship_mesh(ctx),
+                            None,
+                            Some(self.kills as f32 * 1.10),
+                            attack_cd,
+                            Some(ai_to_use),
+                        ),
+                    };
+                    if kind == EnemyKind::Carrier {
+                        self.carriers.insert(enemy.id, Carrier::default());
+                    }
+                    if kind == EnemyKind::Standard
+                        && rng.gen::<f32>() < get_shield_chance(wave_count)
+                    {
+                        self.shields
+                            .insert(enemy.id, Shield::new((enemy.x, enemy.y), player_coords));
+                    }
+                    if kind == EnemyKind::MineLayer {
+                        self.mine_layers.insert(enemy.id, MINE_LAYER_DROP_INTERVAL);
+                    }
+                    if kind == EnemyKind::Splitter {
+                        self.splitters.insert(
+                            enemy.id,
+                            Splitter {
+                                depth: 0,
+                                max_hp: enemy.hp,
+                                personality: splitter_personality,
+                            },
+                        );
+                    }
+                    let perception = Perception::new(
+                        self.assets
+                            .perception_profiles
+                            .get_profile(perception_profile),
                     );
+                    self.perceptions.insert(enemy.id, perception);
                     self.enemy.push(enemy);
                 }
             }
@@ -772,6 +2219,188 @@ impl GameState {
 
         Ok(())
     }
+    /// Prints the state path of every enemy whose AI has one
+    fn dump_enemy_states(&self) {
+        for enemy in &self.enemy {
+            if let Some(path) = enemy.ai.as_ref().and_then(|ai| ai.get_state_path()) {
+                println!("enemy {}: {}", enemy.id, path);
+            }
+        }
+    }
+    fn select_next_enemy(&mut self) {
+        let next = match self
+            .selected_enemy
+            .and_then(|id| self.enemy.iter().position(|enemy| enemy.id == id))
+        {
+            Some(i) => self.enemy.get(i + 1),
+            None => self.enemy.first(),
+        };
+        self.selected_enemy = next.map(|enemy| enemy.id);
+    }
+
+    fn dump_ai_traces(&self) {
+        let traces: Vec<(EntityId, &TraceLog)> = self
+            .enemy
+            .iter()
+            .filter_map(|enemy| {
+                let trace = enemy.ai.as_ref().and_then(|ai| ai.get_trace())?;
+                Some((enemy.id, trace))
+            })
+            .collect();
+        match dump_traces(AI_TRACE_PATH, &traces) {
+            Ok(_) => println!("AI traces written to {}", AI_TRACE_PATH),
+            Err(e) => println!("Error writing AI traces: {:?}", e),
+        }
+    }
+
+    fn draw_ai_debug(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        for enemy in &self.enemy {
+            let ai = match &enemy.ai {
+                Some(ai) => ai,
+                None => continue,
+            };
+            let debug_info = ai.get_debug_info();
+            let label = match (&debug_info, ai.get_state_path()) {
+                (Some(info), _) => match info.node_id {
+                    Some(node_id) => format!("{:?} #{}", info.behavior, node_id),
+                    None => format!("{:?}", info.behavior),
+                },
+                (None, Some(path)) => path,
+                (None, None) => continue,
+            };
+            let position = Point2::from([enemy.x, enemy.y]);
+            Text::new(label).draw(canvas, Point2::from([enemy.x, enemy.y - 20.0]));
+
+            let movement_target = match enemy.steering_goal {
+                Some(SteeringGoal::Seek(target)) | Some(SteeringGoal::Arrive(target)) => {
+                    Some(target)
+                }
+                Some(SteeringGoal::PursuePlayer) | Some(SteeringGoal::EvadePlayer) => {
+                    Some((self.player.x, self.player.y))
+                }
+                _ => None,
+            };
+            let aim_target = self
+                .attacking_enemies
+                .get(&enemy.id)
+                .map(|order| order.target);
+            for (target, color) in [(movement_target, Color::GREEN), (aim_target, Color::RED)] {
+                if let Some(target) = target {
+                    if target != (enemy.x, enemy.y) {
+                        let line = Mesh::new_line(
+                            ctx,
+                            &[position, Point2::from([target.0, target.1])],
+                            1.0,
+                            color,
+                        )?;
+                        canvas.draw(&line, Point2::from([0.0, 0.0]));
+                    }
+                }
+            }
+
+            if self.selected_enemy != Some(enemy.id) {
+                continue;
+            }
+            let marker = Mesh::new_circle(
+                ctx,
+                DrawMode::stroke(2.0),
+                position,
+                40.0,
+                1.0,
+                Color::YELLOW,
+            )?;
+            canvas.draw(&marker, Point2::from([0.0, 0.0]));
+            if let Some(info) = debug_info {
+                let mut y = 300.0;
+                for node in &info.tree {
+                    let mut depth = 0;
+                    let mut parent = node.get_parent();
+                    while let Some(parent_id) = parent {
+                        depth += 1;
+                        parent = info
+                            .tree
+                            .iter()
+                            .find(|node| node.get_id() == parent_id)
+                            .and_then(|node| node.get_parent());
+                    }
+                    let color = match info.node_id == Some(node.get_id()) {
+                        true => Color::YELLOW,
+                        false => Color::WHITE,
+                    };
+                    let text = Text::new(format!(
+                        "{}#{} {:?}",
+                        "  ".repeat(depth),
+                        node.get_id(),
+                        node.get_name()
+                    ));
+                    canvas.draw(
+                        &text,
+                        DrawParam::default()
+                            .dest(Point2::from([10.0, y]))
+                            .color(color),
+                    );
+                    y += 18.0;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_boss_status(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        boss_state: &BossState,
+    ) -> GameResult {
+        let name = &boss_state.definition.name;
+        if boss_state.is_defeated() {
+            let mut defeat_text = Text::new(format!("{} destroyed!", name));
+            defeat_text.set_scale(50.0);
+            defeat_text.draw(canvas, Point2::from([700.0, 400.0]));
+            return Ok(());
+        }
+        if boss_state.is_in_intro() {
+            let mut intro_text = Text::new(format!("WARNING: {} approaching", name));
+            intro_text.set_scale(50.0);
+            intro_text.draw(canvas, Point2::from([600.0, 400.0]));
+        }
+
+        let boss_hp = self
+            .enemy
+            .iter()
+            .find(|enemy| enemy.actor_type == ActorType::BossEnemy)
+            .map_or(0.0, |boss| boss.hp.max(0.0));
+        let bar = Rect::new(560.0, 100.0, 800.0, 16.0);
+        let bar_background = Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            bar,
+            Color::from_rgba(60, 60, 60, 200),
+        )?;
+        canvas.draw(&bar_background, Point2::from([0.0, 0.0]));
+        let fill = Rect::new(bar.x, bar.y, bar.w * boss_hp / boss_state.max_hp, bar.h);
+        if fill.w > 0.0 {
+            let fill_color = match boss_state.is_enraged() {
+                true => Color::from_rgb(250, 60, 20),
+                false => Color::from_rgb(200, 30, 60),
+            };
+            let bar_fill = Mesh::new_rectangle(ctx, DrawMode::fill(), fill, fill_color)?;
+            canvas.draw(&bar_fill, Point2::from([0.0, 0.0]));
+        }
+        let mut status = format!(
+            "{} - Phase {}/{}",
+            name,
+            boss_state.phase + 1,
+            boss_state.definition.phases.len()
+        );
+        if boss_state.is_enraged() {
+            status.push_str(" - ENRAGED");
+        }
+        let status_text = Text::new(status);
+        status_text.draw(canvas, Point2::from([bar.x, bar.y - 20.0]));
+        Ok(())
+    }
+
     fn draw_game(&mut self, ctx: &mut Context) -> GameResult {
         let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
         let fps = Text::new(format!("FPS: {:.2}", ctx.time.fps()));
@@ -817,6 +2446,33 @@ impl GameState {
 
         fps.draw(&mut canvas, Point2::from([10.0, 10.0]));
         kill_count.draw(&mut canvas, Point2::from([1500.0, 30.0]));
+        let score = Text::new(format!("Score: {}", self.score));
+        score.draw(&mut canvas, Point2::from([1500.0, 50.0]));
+        let player_settings = self.difficulty.player_settings();
+        let hp = Text::new(format!(
+            "HP: {:.0}/{:.0}",
+            self.player.hp.max(0.0),
+            player_settings.max_hp
+        ));
+        hp.draw(&mut canvas, Point2::from([10.0, 1000.0]));
+        let shield = Text::new(format!(
+            "Shield: {:.0}/{:.0}",
+            self.player_status.shield, player_settings.max_shield
+        ));
+        shield.draw(&mut canvas, Point2::from([10.0, 1020.0]));
+        let lives = Text::new(format!("Lives: {}", self.player_status.lives));
+        lives.draw(&mut canvas, Point2::from([10.0, 1040.0]));
+        let mut effect_y_pos = 90.0;
+        for (pickup_type, remaining) in &self.pickup_effects {
+            let mut effect_text = Text::new(format!(
+                "{}: {:.1}s",
+                pickup_type.label(),
+                remaining / 1000.0
+            ));
+            effect_text.set_scale(18.0);
+            effect_text.draw(&mut canvas, Point2::from([1700.0, effect_y_pos]));
+            effect_y_pos += 20.0;
+        }
         let mut damage_modifier = ((self.kills / 10) as f32).floor() * 1.25;
         if damage_modifier == 0.0 {
             damage_modifier = 1.0;
@@ -831,6 +2487,22 @@ impl GameState {
         alt_damage.draw(&mut canvas, Point2::from([1700.0, 50.0]));
         let alt_cd = Text::new(format!("Power Atk CD: {:.2}ms", self.alt_cd));
         alt_cd.draw(&mut canvas, Point2::from([1700.0, 70.0]));
+        let dash_cd = Text::new(format!(
+            "Dash CD: {:.2}ms / {:.0}ms",
+            self.player_status.dash_cd, DASH_COOLDOWN
+        ));
+        dash_cd.draw(&mut canvas, Point2::from([10.0, 980.0]));
+        if let Some(autopilot) = &self.autopilot {
+            let autopilot_text = Text::new(format!(
+                "Autopilot: {} (F6 to take over)",
+                autopilot.skill.label()
+            ));
+            autopilot_text.draw(&mut canvas, Point2::from([10.0, 960.0]));
+        }
+
+        if let Some(boss_state) = &self.boss_state {
+            self.draw_boss_status(ctx, &mut canvas, boss_state)?;
+        }
 
         if self.player.hp <= 0.0 {
             let mut game_over_text = Text::new("Game Over");
@@ -840,9 +2512,29 @@ impl GameState {
             kill_count.draw(&mut canvas, Point2::from([900.0, 580.0]));
             self.enemy.drain(..);
             self.projectiles.drain(..);
+            self.pickups.drain(..);
             return canvas.finish(ctx);
         }
+        for hazard in &self.hazards {
+            hazard
+                .bounding_box
+                .draw(&mut canvas, Point2::from([hazard.x, hazard.y]));
+        }
+        // Blink the player while the respawn invulnerability is active
+        let is_blinking = self.player_status.invulnerable_cd > 0.0
+            && ((self.player_status.invulnerable_cd / 100.0) as u32).is_multiple_of(2);
+        if let Some(image) = &self.player.image {
+            for (x, y, remaining) in &self.player_status.dash_trail {
+                canvas.draw(
+                    image,
+                    DrawParam::new()
+                        .dest(Point2::from([*x, *y]))
+                        .color(Color::new(0.4, 0.8, 0.9, remaining / DASH_TRAIL_LIFETIME)),
+                );
+            }
+        }
         match &self.player.image {
+            _ if is_blinking => (),
             Some(image) => {
                 canvas.draw(image, Point2::from([self.player.x, self.player.y]));
             }
@@ -864,6 +2556,60 @@ impl GameState {
             }
         });
 
+        // Telegraph the path of every ram that is winding up
+        for (id, ram) in &self.rams {
+            if ram.phase != RamPhase::WindUp {
+                continue;
+            }
+            let enemy = match self.enemy.iter().find(|enemy| enemy.id == *id) {
+                Some(enemy) => enemy,
+                None => continue,
+            };
+            let charge_end = (
+                enemy.x + ram.direction.0 * ram.distance,
+                enemy.y + ram.direction.1 * ram.distance,
+            );
+            let line = Mesh::new_line(
+                ctx,
+                &[
+                    Point2::from([enemy.x, enemy.y]),
+                    Point2::from([charge_end.0, charge_end.1]),
+                ],
+                3.0,
+                Color::new(1.0, 0.6, 0.1, 0.5),
+            )?;
+            canvas.draw(&line, Point2::from([0.0, 0.0]));
+        }
+        for enemy in &self.enemy {
+            if let Some(shield) = self.shields.get(&enemy.id) {
+                let arc = Mesh::new_line(
+                    ctx,
+                    &shield.get_arc_points((enemy.x, enemy.y)),
+                    4.0,
+                    Color::new(0.4, 0.8, 1.0, 0.8),
+                )?;
+                canvas.draw(&arc, Point2::from([0.0, 0.0]));
+            }
+        }
+        for blast in &self.blasts {
+            let progress = 1.0 - blast.remaining / BLAST_DURATION;
+            let ring = Mesh::new_circle(
+                ctx,
+                DrawMode::stroke(4.0),
+                Point2::from([blast.x, blast.y]),
+                (blast.radius * progress).max(1.0),
+                1.0,
+                Color::new(1.0, 0.6, 0.1, 1.0 - progress),
+            )?;
+            canvas.draw(&ring, Point2::from([0.0, 0.0]));
+        }
+
+        let _ = &self.pickups.iter().for_each(|pickup| {
+            pickup
+                .bounding_box
+                .draw(&mut canvas, Point2::from([pickup.x, pickup.y]));
+        });
+
         let _ = &self.projectiles.iter().for_each(|projectile| {
             projectile
                 .bounding_box
@@ -878,6 +2624,9 @@ impl GameState {
                 Err(e) => println!("Error playing bgm: {:?}", e),
             }
         }
+        if self.is_ai_debug_visible {
+            self.draw_ai_debug(ctx, &mut canvas)?;
+        }
         if self.kills > 30 && self.kills < 60 {
             let mut alert_text = Text::new(format!("Special Attack Unlocked! (RMB)"));
             alert_text.set_scale(40.0);
@@ -890,11 +2639,33 @@ impl GameState {
 
 impl event::EventHandler<GameError> for GameState {
     fn update(&mut self, ctx: &mut Context) -> GameResult {
+        self.exchange_with_bot(ctx);
         match self.play_state {
             PlayState::MainGame => self.update_game(ctx),
             PlayState::MainMenu => {
+                // The autopilot starts the game on its own, handy for attract mode
+                if self.autopilot.is_some() && !self.keys_pressed.contains(&KeyCode::Return) {
+                    self.apply_bot_input(ctx, BotInput::KeyDown(KeyCode::Return));
+                }
+                if self.keys_pressed.contains(&KeyCode::Key1) {
+                    self.difficulty = Difficulty::Easy;
+                } else if self.keys_pressed.contains(&KeyCode::Key2) {
+                    self.difficulty = Difficulty::Normal;
+                } else if self.keys_pressed.contains(&KeyCode::Key3) {
+                    self.difficulty = Difficulty::Hard;
+                }
                 if self.keys_pressed.contains(&KeyCode::Return) {
+                    let player_settings = self.difficulty.player_settings();
+                    self.player.hp = player_settings.max_hp;
+                    self.player_status = PlayerStatus::new(&player_settings);
+                    self.player_model = PlayerModel::new(
+                        self.assets.adaptation_settings.clone(),
+                        self.difficulty.max_adaptation(),
+                    );
                     self.play_state = PlayState::MainGame;
+                    if self.autopilot.is_some() {
+                        self.apply_bot_input(ctx, BotInput::KeyUp(KeyCode::Return));
+                    }
                 }
                 Ok(())
             }
@@ -912,6 +2683,19 @@ impl event::EventHandler<GameError> for GameState {
                 let mut start_text = Text::new("Press Enter to Start");
                 start_text.set_scale(30.0);
                 start_text.draw(&mut canvas, Point2::from([800.0, 400.0]));
+                let mut difficulty_text = Text::new(format!(
+                    "Difficulty: {} (1: Easy, 2: Normal, 3: Hard)",
+                    self.difficulty.label()
+                ));
+                difficulty_text.set_scale(24.0);
+                difficulty_text.draw(&mut canvas, Point2::from([800.0, 460.0]));
+                let dash_key_text = match self.is_rebinding_dash {
+                    true => "Dash: press a free key to bind".to_string(),
+                    false => format!("Dash: {:?} (F1 to rebind)", self.dash_key),
+                };
+                let mut dash_key_text = Text::new(dash_key_text);
+                dash_key_text.set_scale(24.0);
+                dash_key_text.draw(&mut canvas, Point2::from([800.0, 500.0]));
                 canvas.finish(ctx)
             }
         }
@@ -953,6 +2737,35 @@ impl event::EventHandler<GameError> for GameState {
                         // Exit the game when the escape key is pressed
                         ctx.request_quit();
                     }
+                    // Keep waiting until a key that isn't taken comes in
+                    _ if self.is_rebinding_dash && RESERVED_KEYS.contains(&key) => (),
+                    _ if self.is_rebinding_dash => {
+                        self.dash_key = key;
+                        self.is_rebinding_dash = false;
+                    }
+                    KeyCode::F1 if matches!(self.play_state, PlayState::MainMenu) => {
+                        self.is_rebinding_dash = true;
+                    }
+                    KeyCode::F3 if matches!(self.play_state, PlayState::MainGame) => {
+                        self.dump_enemy_states();
+                    }
+                    KeyCode::F4 if matches!(self.play_state, PlayState::MainGame) => {
+                        self.is_ai_debug_visible = !self.is_ai_debug_visible;
+                    }
+                    KeyCode::F5 if matches!(self.play_state, PlayState::MainGame) => {
+                        self.dump_ai_traces();
+                    }
+                    KeyCode::F6 if matches!(self.play_state, PlayState::MainGame) => {
+                        self.toggle_autopilot(ctx);
+                    }
+                    KeyCode::Tab if self.is_ai_debug_visible => {
+                        self.select_next_enemy();
+                    }
+                    _ if key == self.dash_key => {
+                        if matches!(self.play_state, PlayState::MainGame) && self.player.hp > 0.0 {
+                            self.start_dash(ctx);
+                        }
+                    }
                     _ => {
                         self.keys_pressed.insert(key);
                     }
@@ -977,7 +2790,12 @@ impl event::EventHandler<GameError> for GameState {
     }
 }
 
-pub fn build_main_game_state(mut ctx: Context, assets: Assets) -> (Context, GameState) {
+pub fn build_main_game_state(
+    mut ctx: Context,
+    assets: Assets,
+    autopilot_skill: Option<AutopilotSkill>,
+    bot_server: Option<BotServer>,
+) -> (Context, GameState) {
     let enemy = create_enemy(
         900.0,
         100.0,
@@ -995,17 +2813,48 @@ pub fn build_main_game_state(mut ctx: Context, assets: Assets) -> (Context, Game
         create_spaceship_mesh(&mut ctx),
         Some(assets.player_ship.clone()),
     );
+    let director = Director::new(assets.director_settings.clone());
+    let player_model = PlayerModel::new(
+        assets.adaptation_settings.clone(),
+        Difficulty::Normal.max_adaptation(),
+    );
     let state = GameState {
         dt: Duration::new(0, 0),
+        game_time: Duration::new(0, 0),
         assets,
         player,
+        player_status: PlayerStatus::new(&Difficulty::Normal.player_settings()),
+        difficulty: Difficulty::Normal,
         enemy: vec![enemy],
+        boss_state: None,
         projectiles: vec![],
+        pickups: vec![],
+        pickup_effects: HashMap::new(),
         keys_pressed: HashSet::new(),
+        dash_key: KeyCode::LShift,
+        is_rebinding_dash: false,
+        is_ai_debug_visible: false,
+        selected_enemy: None,
+        autopilot: autopilot_skill.map(|skill| Autopilot::new(skill, rand::random())),
+        autopilot_skill: autopilot_skill.unwrap_or(AutopilotSkill::Average),
+        bot_server,
         kills: 0,
+        score: 0,
         alt_cd: 0.0,
         game_state_data: std::collections::HashMap::new(),
-        attacking_enemies: vec![],
+        attacking_enemies: HashMap::new(),
+        rams: HashMap::new(),
+        blasts: vec![],
+        carriers: HashMap::new(),
+        orphaned_fighters: HashMap::new(),
+        splitters: HashMap::new(),
+        shields: HashMap::new(),
+        mine_layers: HashMap::new(),
+        hazards: vec![],
+        perceptions: HashMap::new(),
+        squad_coordinator: SquadCoordinator::new(get_max_attack_tokens(1.0)),
+        director,
+        player_model,
         play_state: PlayState::MainMenu,
     };
     (ctx, state)
@@ -0,0 +1,598 @@
+use crate::behaviors::enemy_ai::model::EnemyAi;
+use crate::behaviors::enemy_ai::world_view::{
+    ActorView, AiCommand, Faction, ProjectileView, WaveInfo, WeaponId, WorldView,
+    NEARBY_ALLY_RADIUS,
+};
+use crate::behaviors::steering::{
+    apply_steering_force, get_steering_force, SteeringAgent, SteeringGoal,
+};
+use crate::entity::{next_entity_id, EntityId};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Rules of a headless fight, loosely matching the real game's numbers
+#[derive(Debug, Clone)]
+pub struct SimSettings {
+    pub arena: (f32, f32),
+    // ms per step
+    pub dt: f32,
+    // ms before the fight is called off
+    pub max_time: f32,
+    pub enemy_count: usize,
+    pub player_hp: f32,
+    pub player_speed: f32,
+    // ms between player shots
+    pub player_fire_interval: f32,
+    pub enemy_hp: f32,
+    pub enemy_speed: f32,
+    pub enemy_fire_cooldown: f32,
+    // enemies aim up to this far off their target
+    pub enemy_aim_spread: f32,
+    pub projectile_speed: f32,
+    pub player_damage: f32,
+    pub enemy_damage: f32,
+    pub hit_radius: f32,
+}
+
+impl Default for SimSettings {
+    fn default() -> Self {
+        SimSettings {
+            arena: (1920.0, 1080.0),
+            dt: 16.0,
+            max_time: 60000.0,
+            enemy_count: 4,
+            player_hp: 100.0,
+            player_speed: 400.0,
+            player_fire_interval: 300.0,
+            enemy_hp: 150.0,
+            enemy_speed: 200.0,
+            enemy_fire_cooldown: 800.0,
+            enemy_aim_spread: 150.0,
+            projectile_speed: 600.0,
+            player_damage: 10.0,
+            enemy_damage: 5.0,
+            hit_radius: 30.0,
+        }
+    }
+}
+
+/// What the player does for one step
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PlayerInput {
+    // direction to move in, scaled down to unit length when longer
+    pub movement: (f32, f32),
+    pub aim: (f32, f32),
+    pub is_firing: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimActor {
+    pub id: EntityId,
+    pub position: (f32, f32),
+    pub velocity: (f32, f32),
+    pub max_speed: f32,
+    pub hp: f32,
+    pub attack_cooldown: f32,
+    pub steering_goal: SteeringGoal,
+}
+
+impl SimActor {
+    fn get_view(&self) -> ActorView {
+        ActorView {
+            id: self.id,
+            position: self.position,
+            velocity: self.velocity,
+            max_speed: self.max_speed,
+            hp: self.hp,
+            attack_cooldown: Some(self.attack_cooldown.max(0.0)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimProjectile {
+    pub owner: EntityId,
+    pub position: (f32, f32),
+    pub velocity: (f32, f32),
+    pub faction: Faction,
+}
+
+/// Something that happened during the last step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimEvent {
+    Shot(EntityId),
+    Hit {
+        owner: EntityId,
+        target: EntityId,
+        damage: f32,
+    },
+    Killed(EntityId),
+}
+
+/// Running totals of a fight
+#[derive(Debug, Clone, Default)]
+pub struct FightStats {
+    pub damage_to_player: f32,
+    pub damage_to_enemies: f32,
+    pub enemy_shots: u32,
+    pub enemy_hits: u32,
+    // ms each enemy stayed alive, filled in as they die or the fight ends
+    pub enemy_lifetimes: Vec<f32>,
+    // (time, amount) of every hit on the player
+    pub player_hits: Vec<(f32, f32)>,
+}
+
+pub struct SimEnemy {
+    pub actor: SimActor,
+    pub ai: Box<dyn EnemyAi>,
+    // weapon and target of a shot waiting for the cooldown, like the game's attack orders
+    pending_attack: Option<(WeaponId, (f32, f32))>,
+    spawned_at: f32,
+}
+
+/// A fight between a player and a group of enemies without any rendering or audio, for
+/// running offline as fast as the CPU allows. Every random choice comes from the seed.
+pub struct Simulation {
+    pub settings: SimSettings,
+    pub rng: StdRng,
+    // ms since the fight started
+    pub time: f32,
+    pub player: SimActor,
+    pub enemies: Vec<SimEnemy>,
+    pub projectiles: Vec<SimProjectile>,
+    pub stats: FightStats,
+    pub events: Vec<SimEvent>,
+    since_player_shot: f32,
+}
+
+impl Simulation {
+    pub fn new(settings: SimSettings, seed: u64) -> Simulation {
+        let player = SimActor {
+            id: next_entity_id(),
+            position: (settings.arena.0 / 2.0, settings.arena.1 * 0.8),
+            velocity: (0.0, 0.0),
+            max_speed: settings.player_speed,
+            hp: settings.player_hp,
+            attack_cooldown: 0.0,
+            steering_goal: SteeringGoal::Hold,
+        };
+        Simulation {
+            rng: StdRng::seed_from_u64(seed),
+            time: 0.0,
+            player,
+            enemies: vec![],
+            projectiles: vec![],
+            stats: FightStats::default(),
+            events: vec![],
+            since_player_shot: settings.player_fire_interval,
+            settings,
+        }
+    }
+
+    /// Adds an enemy at a random spot in the top half of the arena
+    pub fn spawn_enemy(&mut self, ai: Box<dyn EnemyAi>) {
+        let position = (
+            self.rng.gen_range(0.0..self.settings.arena.0),
+            self.rng.gen_range(0.0..self.settings.arena.1 / 2.0),
+        );
+        let attack_cooldown = self.rng.gen_range(500.0..2000.0);
+        self.enemies.push(SimEnemy {
+            actor: SimActor {
+                id: next_entity_id(),
+                position,
+                velocity: (0.0, 0.0),
+                max_speed: self.settings.enemy_speed,
+                hp: self.settings.enemy_hp,
+                attack_cooldown,
+                steering_goal: SteeringGoal::Hold,
+            },
+            ai,
+            pending_attack: None,
+            spawned_at: self.time,
+        });
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.player.hp <= 0.0 || self.enemies.is_empty() || self.time >= self.settings.max_time
+    }
+
+    pub fn is_player_weapon_ready(&self) -> bool {
+        self.since_player_shot >= self.settings.player_fire_interval
+    }
+
+    /// Records how long the remaining enemies lasted, call once the fight is over
+    pub fn finish(&mut self) {
+        for enemy in self.enemies.drain(..) {
+            self.stats
+                .enemy_lifetimes
+                .push(self.time - enemy.spawned_at);
+        }
+    }
+
+    /// Advances the fight by one `dt`
+    pub fn step(&mut self, input: PlayerInput) {
+        let dt = self.settings.dt;
+        self.time += dt;
+        self.events.clear();
+        self.move_player(input, dt);
+        self.update_enemies(dt);
+        self.update_projectiles(dt);
+    }
+
+    fn move_player(&mut self, input: PlayerInput, dt: f32) {
+        let length = (input.movement.0.powi(2) + input.movement.1.powi(2)).sqrt();
+        let direction = match length > 1.0 {
+            true => (input.movement.0 / length, input.movement.1 / length),
+            false => input.movement,
+        };
+        let speed = self.player.max_speed;
+        self.player.velocity = (direction.0 * speed, direction.1 * speed);
+        self.player.position = (
+            (self.player.position.0 + self.player.velocity.0 * dt / 1000.0)
+                .clamp(0.0, self.settings.arena.0),
+            (self.player.position.1 + self.player.velocity.1 * dt / 1000.0)
+                .clamp(0.0, self.settings.arena.1),
+        );
+
+        self.since_player_shot += dt;
+        if input.is_firing && self.since_player_shot >= self.settings.player_fire_interval {
+            self.since_player_shot = 0.0;
+            let projectile = self.create_projectile(
+                self.player.id,
+                self.player.position,
+                input.aim,
+                Faction::Player,
+            );
+            self.projectiles.push(projectile);
+            self.events.push(SimEvent::Shot(self.player.id));
+        }
+    }
+
+    fn create_projectile(
+        &self,
+        owner: EntityId,
+        origin: (f32, f32),
+        target: (f32, f32),
+        faction: Faction,
+    ) -> SimProjectile {
+        let direction = (target.0 - origin.0, target.1 - origin.1);
+        let length = (direction.0.powi(2) + direction.1.powi(2))
+            .sqrt()
+            .max(f32::EPSILON);
+        SimProjectile {
+            owner,
+            position: origin,
+            velocity: (
+                direction.0 / length * self.settings.projectile_speed,
+                direction.1 / length * self.settings.projectile_speed,
+            ),
+            faction,
+        }
+    }
+
+    fn update_enemies(&mut self, dt: f32) {
+        let player_view = self.player.get_view();
+        let player_agent = SteeringAgent {
+            position: self.player.position,
+            velocity: self.player.velocity,
+            max_speed: self.player.max_speed,
+        };
+        let projectile_views: Vec<ProjectileView> = self
+            .projectiles
+            .iter()
+            .map(|projectile| ProjectileView {
+                position: projectile.position,
+                velocity: projectile.velocity,
+                faction: projectile.faction,
+            })
+            .collect();
+        let enemy_views: Vec<ActorView> = self
+            .enemies
+            .iter()
+            .map(|enemy| enemy.actor.get_view())
+            .collect();
+        let wave = WaveInfo {
+            wave: 1,
+            enemies_remaining: self.enemies.len(),
+            is_boss_wave: false,
+        };
+
+        let mut new_projectiles = vec![];
+        for i in 0..self.enemies.len() {
+            let actor = enemy_views[i];
+            let allies: Vec<ActorView> = enemy_views
+                .iter()
+                .filter(|ally| {
+                    ally.id != actor.id
+                        && ((ally.position.0 - actor.position.0).powi(2)
+                            + (ally.position.1 - actor.position.1).powi(2))
+                        .sqrt()
+                            < NEARBY_ALLY_RADIUS
+                })
+                .copied()
+                .collect();
+            let world = WorldView {
+                current_time: self.time as u128,
+                actor,
+                player: player_view,
+                projectiles: &projectile_views,
+                // the simulation has no mine-layers
+                hazards: &[],
+                allies: &allies,
+                arena: self.settings.arena,
+                wave,
+            };
+            let commands = match self.enemies[i].ai.perform_action(&world) {
+                Ok(commands) => commands,
+                Err(e) => {
+                    println!("Error performing action: {:?}", e);
+                    continue;
+                }
+            };
+            let mut aim = None;
+            let mut weapon = None;
+            for command in commands {
+                match command {
+                    AiCommand::Move(goal) => self.enemies[i].actor.steering_goal = goal,
+                    AiCommand::Aim(target) => aim = Some(target),
+                    AiCommand::Fire(weapon_id) => weapon = Some(weapon_id),
+                    AiCommand::Sound(_) => (),
+                    // no ram maneuver here, the enemy just goes straight for the target
+                    AiCommand::Ram(target) => {
+                        self.enemies[i].actor.steering_goal = SteeringGoal::Seek(target)
+                    }
+                }
+            }
+
+            let enemy = &mut self.enemies[i];
+            if let (Some(weapon), None) = (weapon, enemy.pending_attack) {
+                enemy.pending_attack = Some((weapon, aim.unwrap_or(self.player.position)));
+            }
+            enemy.actor.attack_cooldown = (enemy.actor.attack_cooldown - dt).max(0.0);
+            let pending_attack = match enemy.actor.attack_cooldown <= 0.0 {
+                true => enemy.pending_attack.take(),
+                false => None,
+            };
+            if let Some((weapon, target)) = pending_attack {
+                enemy.actor.attack_cooldown = self.settings.enemy_fire_cooldown;
+                let spread = self.settings.enemy_aim_spread;
+                let target = (
+                    target.0 + self.rng.gen_range(-spread..=spread),
+                    target.1 + self.rng.gen_range(-spread..=spread),
+                );
+                let origin = enemy.actor.position;
+                let base_angle = (target.1 - origin.1).atan2(target.0 - origin.0);
+                let spread_angles: &[f32] = match weapon {
+                    WeaponId::Blaster => &[0.0],
+                    WeaponId::Spread => &[-15.0, 0.0, 15.0],
+                };
+                for spread_angle in spread_angles {
+                    let angle = base_angle + spread_angle.to_radians();
+                    new_projectiles.push((
+                        enemy.actor.id,
+                        origin,
+                        (origin.0 + angle.cos(), origin.1 + angle.sin()),
+                    ));
+                    self.stats.enemy_shots += 1;
+                }
+            }
+
+            let agent = SteeringAgent {
+                position: enemy.actor.position,
+                velocity: enemy.actor.velocity,
+                max_speed: enemy.actor.max_speed,
+            };
+            let neighbors: Vec<SteeringAgent> = allies
+                .iter()
+                .map(|ally| SteeringAgent {
+                    position: ally.position,
+                    velocity: ally.velocity,
+                    max_speed: ally.max_speed,
+                })
+                .collect();
+            let weights = enemy.ai.get_steering_weights();
+            let force = get_steering_force(
+                &agent,
+                enemy.actor.steering_goal,
+                &weights,
+                &neighbors,
+                &player_agent,
+            );
+            enemy.actor.velocity = apply_steering_force(&agent, force, &weights, dt / 1000.0);
+            // no hiding off screen until the time runs out
+            enemy.actor.position = (
+                (enemy.actor.position.0 + enemy.actor.velocity.0 * dt / 1000.0)
+                    .clamp(0.0, self.settings.arena.0),
+                (enemy.actor.position.1 + enemy.actor.velocity.1 * dt / 1000.0)
+                    .clamp(0.0, self.settings.arena.1),
+            );
+        }
+        for (owner, origin, target) in new_projectiles {
+            let projectile = self.create_projectile(owner, origin, target, Faction::Enemy);
+            self.projectiles.push(projectile);
+            self.events.push(SimEvent::Shot(owner));
+        }
+    }
+
+    fn update_projectiles(&mut self, dt: f32) {
+        let hit_radius = self.settings.hit_radius;
+        let is_hit = |a: (f32, f32), b: (f32, f32)| {
+            (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2) < hit_radius.powi(2)
+        };
+        let was_player_alive = self.player.hp > 0.0;
+        let mut projectiles = std::mem::take(&mut self.projectiles);
+        projectiles.retain_mut(|projectile| {
+            projectile.position.0 += projectile.velocity.0 * dt / 1000.0;
+            projectile.position.1 += projectile.velocity.1 * dt / 1000.0;
+            match projectile.faction {
+                Faction::Enemy if is_hit(projectile.position, self.player.position) => {
+                    let damage = self.settings.enemy_damage.min(self.player.hp.max(0.0));
+                    self.player.hp -= self.settings.enemy_damage;
+                    self.stats.damage_to_player += damage;
+                    self.stats.enemy_hits += 1;
+                    self.stats.player_hits.push((self.time, damage));
+                    self.events.push(SimEvent::Hit {
+                        owner: projectile.owner,
+                        target: self.player.id,
+                        damage,
+                    });
+                    return false;
+                }
+                Faction::Player => {
+                    let target = self
+                        .enemies
+                        .iter_mut()
+                        .find(|enemy| is_hit(projectile.position, enemy.actor.position));
+                    if let Some(enemy) = target {
+                        enemy.actor.hp -= self.settings.player_damage;
+                        self.stats.damage_to_enemies += self.settings.player_damage;
+                        self.events.push(SimEvent::Hit {
+                            owner: projectile.owner,
+                            target: enemy.actor.id,
+                            damage: self.settings.player_damage,
+                        });
+                        return false;
+                    }
+                }
+                _ => (),
+            }
+            let (x, y) = projectile.position;
+            x >= 0.0 && x <= self.settings.arena.0 && y >= 0.0 && y <= self.settings.arena.1
+        });
+        self.projectiles = projectiles;
+        if was_player_alive && self.player.hp <= 0.0 {
+            self.events.push(SimEvent::Killed(self.player.id));
+        }
+
+        let time = self.time;
+        let lifetimes = &mut self.stats.enemy_lifetimes;
+        let events = &mut self.events;
+        self.enemies.retain(|enemy| {
+            if enemy.actor.hp > 0.0 {
+                return true;
+            }
+            lifetimes.push(time - enemy.spawned_at);
+            events.push(SimEvent::Killed(enemy.actor.id));
+            false
+        });
+    }
+}
+
+/// A stand-in player for headless fights: dodges incoming shots, keeps its distance from the
+/// closest enemy while strafing, and shoots at it. `skill` goes from 0 (sloppy) to 1 (sharp).
+pub struct ScriptedPlayer {
+    pub skill: f32,
+    rng: StdRng,
+    strafe_direction: f32,
+    // ms until the strafe direction may flip
+    strafe_timer: f32,
+}
+
+impl ScriptedPlayer {
+    pub fn new(skill: f32, seed: u64) -> ScriptedPlayer {
+        ScriptedPlayer {
+            skill: skill.clamp(0.0, 1.0),
+            rng: StdRng::seed_from_u64(seed),
+            strafe_direction: 1.0,
+            strafe_timer: 0.0,
+        }
+    }
+
+    pub fn get_input(&mut self, sim: &Simulation) -> PlayerInput {
+        let position = sim.player.position;
+        let dodge_radius = 30.0 + 100.0 * self.skill;
+        // closest enemy projectile that is still heading our way
+        let threat = sim
+            .projectiles
+            .iter()
+            .filter(|projectile| projectile.faction == Faction::Enemy)
+            .filter(|projectile| {
+                let offset = (
+                    position.0 - projectile.position.0,
+                    position.1 - projectile.position.1,
+                );
+                offset.0 * projectile.velocity.0 + offset.1 * projectile.velocity.1 > 0.0
+            })
+            .map(|projectile| {
+                let distance = ((position.0 - projectile.position.0).powi(2)
+                    + (position.1 - projectile.position.1).powi(2))
+                .sqrt();
+                (projectile, distance)
+            })
+            .filter(|(_, distance)| *distance < dodge_radius)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        let target = sim
+            .enemies
+            .iter()
+            .map(|enemy| {
+                let distance = ((position.0 - enemy.actor.position.0).powi(2)
+                    + (position.1 - enemy.actor.position.1).powi(2))
+                .sqrt();
+                (enemy.actor, distance)
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+        self.strafe_timer -= sim.settings.dt;
+        if self.strafe_timer <= 0.0 {
+            self.strafe_timer = self.rng.gen_range(1000.0..3000.0);
+            if self.rng.gen_bool(0.5) {
+                self.strafe_direction = -self.strafe_direction;
+            }
+        }
+
+        let movement = match (threat, target) {
+            (Some((projectile, _)), _) => {
+                // step sideways out of the projectile's path
+                let side = (-projectile.velocity.1, projectile.velocity.0);
+                let offset = (
+                    position.0 - projectile.position.0,
+                    position.1 - projectile.position.1,
+                );
+                match offset.0 * side.0 + offset.1 * side.1 >= 0.0 {
+                    true => side,
+                    false => (-side.0, -side.1),
+                }
+            }
+            (None, Some((enemy, distance))) => {
+                let toward = (enemy.position.0 - position.0, enemy.position.1 - position.1);
+                let strafe = (
+                    -toward.1 * self.strafe_direction,
+                    toward.0 * self.strafe_direction,
+                );
+                match distance {
+                    d if d < 300.0 => (-toward.0 + strafe.0, -toward.1 + strafe.1),
+                    d if d > 450.0 => (toward.0 + strafe.0, toward.1 + strafe.1),
+                    _ => strafe,
+                }
+            }
+            (None, None) => (0.0, 0.0),
+        };
+        let length = (movement.0.powi(2) + movement.1.powi(2)).sqrt();
+        let movement = match length > 0.0 {
+            true => (movement.0 / length, movement.1 / length),
+            false => movement,
+        };
+
+        let aim = match target {
+            Some((enemy, distance)) => {
+                // better players lead their shots
+                let lead = distance / sim.settings.projectile_speed * self.skill;
+                let jitter = (1.0 - self.skill) * 80.0 + 1.0;
+                (
+                    enemy.position.0
+                        + enemy.velocity.0 * lead
+                        + self.rng.gen_range(-jitter..jitter),
+                    enemy.position.1
+                        + enemy.velocity.1 * lead
+                        + self.rng.gen_range(-jitter..jitter),
+                )
+            }
+            None => position,
+        };
+        PlayerInput {
+            movement,
+            aim,
+            is_firing: target.is_some(),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::behaviors::tree_definition::{get_builtin_trees, load_tree_definition, TreeDefinition};
+use std::path::PathBuf;
+
+const USAGE: &str = "usage: trees [--dot <output dir>] [tree.toml ...]";
+
+/// Validates the built-in behavior trees and any tree files passed in, optionally writing
+/// each one as a `.dot` file. Returns the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    let mut dot_dir = None;
+    let mut paths = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dot" => match args.next() {
+                Some(dir) => dot_dir = Some(PathBuf::from(dir)),
+                None => {
+                    println!("{}", USAGE);
+                    return 2;
+                }
+            },
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return 0;
+            }
+            path => paths.push(path.to_string()),
+        }
+    }
+
+    let mut trees = get_builtin_trees();
+    let mut is_valid = true;
+    for path in &paths {
+        match load_tree_definition(path) {
+            Ok(tree) => trees.push(tree),
+            Err(e) => {
+                println!("{}: {}", path, e);
+                is_valid = false;
+            }
+        }
+    }
+
+    for tree in &trees {
+        let issues = tree.validate();
+        match issues.is_empty() {
+            true => println!("{}: ok ({} nodes)", tree.name, tree.nodes.len()),
+            false => {
+                is_valid = false;
+                println!("{}: {} issue(s)", tree.name, issues.len());
+                for issue in issues {
+                    println!("    {}", issue);
+                }
+            }
+        }
+        if let Some(dir) = &dot_dir {
+            if let Err(e) = write_dot(tree, dir) {
+                println!("{}: failed to write dot file: {}", tree.name, e);
+                is_valid = false;
+            }
+        }
+    }
+
+    match is_valid {
+        true => 0,
+        false => 1,
+    }
+}
+
+fn write_dot(tree: &TreeDefinition, dir: &PathBuf) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.dot", tree.name));
+    std::fs::write(&path, tree.to_dot())?;
+    println!("{}: wrote {}", tree.name, path.display());
+    Ok(())
+}